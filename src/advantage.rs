@@ -0,0 +1,161 @@
+use std::error::Error;
+use std::collections::{BTreeMap, HashSet};
+use chrono::NaiveDate;
+use serde::Serialize;
+use crate::Game;
+
+// a module to measure home advantage in points per game, either for one team or for the whole league.
+
+pub const CLOSED_DOORS: [usize; 2] = [2020, 2021]; // seasons played (at least partly) behind closed doors during COVID
+pub const LAST_CLOSED_DOORS_SEASON: usize = CLOSED_DOORS[CLOSED_DOORS.len() - 1]; // worked out at compile time
+
+pub fn points(goals_for: usize, goals_against: usize) -> usize {
+    // the league points a team earns from a single game: 3 for a win, 1 for a draw.
+    if goals_for > goals_against {
+        3
+    } else if goals_for == goals_against {
+        1
+    } else {
+        0
+    }
+}
+
+pub fn home_away_ppg(games: &[Game], team: Option<&String>, season: usize) -> Option<(f64, f64)> {
+    // calculates home and away points per game in a season.
+    // with a team, only that team's games count; with None, every game counts once for the home side and once for the away side.
+    ppg_of(games.iter().filter(|g| g.season == season), team)
+}
+
+fn ppg_of<'a>(games: impl Iterator<Item = &'a Game>, team: Option<&String>) -> Option<(f64, f64)> {
+    // home and away points per game over any set of games, counted the same way as home_away_ppg.
+    let mut home_points = 0;
+    let mut home_games = 0;
+    let mut away_points = 0;
+    let mut away_games = 0;
+    for game in games {
+        let home_side = team.is_none_or(|t| *t == game.home);
+        let away_side = team.is_none_or(|t| *t == game.away);
+        if home_side {
+            home_points += points(game.home_goals, game.away_goals);
+            home_games += 1;
+        }
+        if away_side {
+            away_points += points(game.away_goals, game.home_goals);
+            away_games += 1;
+        }
+    }
+    if home_games == 0 || away_games == 0 {
+        return None; // the team didn't play any of the games
+    }
+    Some((home_points as f64 / home_games as f64, away_points as f64 / away_games as f64))
+}
+
+pub fn home_edge(games: &[Game], team: Option<&String>, season: usize) -> Option<f64> {
+    // home points per game minus away points per game for a season.
+    home_away_ppg(games, team, season).map(|(home, away)| home - away)
+}
+
+pub fn home_edge_trend(games: &[Game], team: Option<&String>, seasons: &[usize]) -> Vec<(usize, f64)> {
+    // the home edge for every season in the range, skipping seasons the team didn't play in.
+    let mut trend = Vec::new();
+    for season in seasons {
+        if let Some(edge) = home_edge(games, team, *season) {
+            trend.push((*season, edge));
+        }
+    }
+    trend
+}
+
+pub fn edge_drop(games: &[Game], teams: &HashSet<String>, seasons: &[usize]) -> Vec<(String, f64)> {
+    // for every team that played behind closed doors, compares their home edge in the games with crowds against the games without.
+    // each game is classed on its own, so the part of 2019-20 played before the suspension counts as a normal season would.
+    // returns the teams sorted by how much home edge they lost, biggest drop first.
+    let mut drops = Vec::new();
+    for team in teams {
+        let in_range = games.iter().filter(|g| seasons.contains(&g.season));
+        let open = ppg_of(in_range.clone().filter(|g| !behind_closed_doors(g)), Some(team));
+        let closed = ppg_of(in_range.filter(|g| behind_closed_doors(g)), Some(team));
+        if let (Some((open_home, open_away)), Some((closed_home, closed_away))) = (open, closed) {
+            drops.push((team.to_string(), (open_home - open_away) - (closed_home - closed_away)));
+        }
+    }
+    drops.sort_by(|a, b| b.1.total_cmp(&a.1));
+    drops
+}
+
+pub const CLOSED_DOORS_FROM: NaiveDate = NaiveDate::from_ymd_opt(2020, 6, 17).unwrap(); // the 2019-20 season restarted behind closed doors on this date
+
+pub fn behind_closed_doors(game: &Game) -> bool {
+    // whether a game was played without fans, from the 2019-20 restart to the end of 2020-21.
+    // this goes by date rather than matchweek: the restart began with two week 28 games postponed from March,
+    // while the rest of weeks 28 and 29 had been played in front of crowds before the suspension.
+    game.date >= CLOSED_DOORS_FROM && game.season <= LAST_CLOSED_DOORS_SEASON
+}
+
+pub fn first_closed_doors_week(games: &[Game]) -> Option<(usize, usize)> {
+    // the first (season, week) whose games were all played behind closed doors, or None if none were.
+    // a matchweek split by the suspension, with some games played before it and some after, doesn't count.
+    let mut weeks: BTreeMap<(usize, usize), bool> = BTreeMap::new();
+    for game in games {
+        let closed = weeks.entry((game.season, game.week)).or_insert(true);
+        *closed &= behind_closed_doors(game);
+    }
+    weeks.into_iter().find(|(_, closed)| *closed).map(|(week, _)| week)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchweekPoint {
+    // one point of the matchweek time series: the rolling rates over the window that ends at this season and week.
+    pub season: usize,
+    pub week: usize,
+    pub games: usize,
+    pub home_win_rate: f64,
+    pub home_goal_diff: f64,
+}
+
+pub fn matchweek_series(games: &[Game], window: usize) -> Vec<MatchweekPoint> {
+    // groups every game by season and matchweek in chronological order, then rolls a window of `window` matchweeks over the whole history.
+    // the rates pool every game in the window, so a matchweek with fewer fixtures doesn't count as much as a full one.
+    let mut totals: BTreeMap<(usize, usize), (usize, usize, i64)> = BTreeMap::new(); // (games, home wins, home goal difference), sorted by season then week
+    for game in games {
+        let week = totals.entry((game.season, game.week)).or_insert((0, 0, 0));
+        week.0 += 1;
+        if game.result == "H" {
+            week.1 += 1;
+        }
+        week.2 += game.home_goals as i64 - game.away_goals as i64;
+    }
+    let weeks: Vec<_> = totals.into_iter().collect();
+    let window = window.max(1);
+    let mut series = Vec::new();
+    for end in 0..weeks.len() {
+        let start = (end + 1).saturating_sub(window);
+        let mut count = 0;
+        let mut home_wins = 0;
+        let mut goal_diff = 0;
+        for (_, w) in &weeks[start..=end] {
+            count += w.0;
+            home_wins += w.1;
+            goal_diff += w.2;
+        }
+        series.push(MatchweekPoint {
+            season: weeks[end].0.0,
+            week: weeks[end].0.1,
+            games: count,
+            home_win_rate: home_wins as f64 / count as f64 * 100.0,
+            home_goal_diff: goal_diff as f64 / count as f64,
+        });
+    }
+    series
+}
+
+pub fn write_matchweek_csv(series: &[MatchweekPoint], path: &str) -> Result<(), Box<dyn Error>> {
+    // exports the matchweek series so it can be opened in a spreadsheet or another tool.
+    let mut wtr = csv::Writer::from_path(path)?;
+    wtr.write_record(["Season_End_Year", "Wk", "Games", "Home_Win_Rate", "Home_Goal_Diff"])?;
+    for point in series {
+        wtr.write_record([point.season.to_string(), point.week.to_string(), point.games.to_string(), format!("{:.4}", point.home_win_rate), format!("{:.4}", point.home_goal_diff)])?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+use chrono::{Datelike, NaiveDate, Weekday};
+use serde::Serialize;
+use crate::Game;
+use crate::advantage::points;
+
+// a module for analyses based on the date each game was played: day of week, month, the festive period, and fixture congestion.
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Breakdown {
+    // result rates and scoring for one group of games, e.g. every game played on a Saturday.
+    pub label: String,
+    pub games: usize,
+    pub home_win_rate: f64,
+    pub draw_rate: f64,
+    pub away_win_rate: f64,
+    pub goals_per_game: f64,
+}
+
+pub fn breakdown(label: String, games: &[&Game]) -> Option<Breakdown> {
+    // computes the result rates and goals per game for a group of games, or None for an empty group, which has no rates.
+    if games.is_empty() {
+        return None;
+    }
+    let mut home_wins = 0;
+    let mut draws = 0;
+    let mut away_wins = 0;
+    let mut goals = 0;
+    for game in games {
+        match game.result.as_str() {
+            "H" => home_wins += 1,
+            "D" => draws += 1,
+            "A" => away_wins += 1,
+            _ => (),
+        }
+        goals += game.home_goals + game.away_goals;
+    }
+    let count = games.len() as f64;
+    Some(Breakdown {
+        label,
+        games: games.len(),
+        home_win_rate: home_wins as f64 / count * 100.0,
+        draw_rate: draws as f64 / count * 100.0,
+        away_win_rate: away_wins as f64 / count * 100.0,
+        goals_per_game: goals as f64 / count,
+    })
+}
+
+pub fn by_weekday(games: &[Game]) -> Vec<Breakdown> {
+    // result rates for each day of the week, Monday first. days with no games are left out.
+    let days = [Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri, Weekday::Sat, Weekday::Sun];
+    let mut breakdowns = Vec::new();
+    for day in days {
+        let day_games: Vec<&Game> = games.iter().filter(|g| g.date.weekday() == day).collect();
+        breakdowns.extend(breakdown(day.to_string(), &day_games));
+    }
+    breakdowns
+}
+
+pub fn by_month(games: &[Game]) -> Vec<Breakdown> {
+    // result rates for each calendar month, in the order of a season (August to July).
+    let names = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+    let mut breakdowns = Vec::new();
+    for month in (8..=12).chain(1..=7) {
+        let month_games: Vec<&Game> = games.iter().filter(|g| g.date.month() == month).collect();
+        breakdowns.extend(breakdown(names[month as usize - 1].to_string(), &month_games));
+    }
+    breakdowns
+}
+
+pub fn is_festive(date: NaiveDate) -> bool {
+    // the festive fixture period: the busy run of games from the weekend before Christmas to the first days of January.
+    (date.month() == 12 && date.day() >= 20) || (date.month() == 1 && date.day() <= 4)
+}
+
+pub fn festive_split(games: &[Game]) -> (Option<Breakdown>, Option<Breakdown>) {
+    // result rates inside the festive period compared to the rest of the season. either is None if no games fall in it.
+    let (festive, rest): (Vec<&Game>, Vec<&Game>) = games.iter().partition(|g| is_festive(g.date));
+    (breakdown("Festive".to_string(), &festive), breakdown("Rest of season".to_string(), &rest))
+}
+
+pub fn rest_days(games: &[Game]) -> Vec<(Option<i64>, Option<i64>)> {
+    // for every game (in the same order as `games`), the days since the home team's and the away team's previous league game.
+    // a team's first game of each season has no previous game, so its rest is None rather than the length of the summer break.
+    let mut order: Vec<usize> = (0..games.len()).collect();
+    order.sort_by_key(|i| games[*i].date);
+    let mut last_played: HashMap<(usize, &String), NaiveDate> = HashMap::new();
+    let mut rests = vec![(None, None); games.len()];
+    for i in order {
+        let game = &games[i];
+        let home_rest = last_played.insert((game.season, &game.home), game.date).map(|d| (game.date - d).num_days());
+        let away_rest = last_played.insert((game.season, &game.away), game.date).map(|d| (game.date - d).num_days());
+        rests[i] = (home_rest, away_rest);
+    }
+    rests
+}
+
+pub fn congestion_bands(games: &[Game]) -> Vec<(String, usize, f64)> {
+    // groups every team appearance by how many days of rest the team had, and returns (band, appearances, points per game).
+    // bands with no appearances are left out.
+    let bands: [(&str, i64, i64); 4] = [("3 days or fewer", 0, 3), ("4-5 days", 4, 5), ("6-7 days", 6, 7), ("8+ days", 8, i64::MAX)];
+    let mut totals = [(0, 0); 4];
+    for (game, (home_rest, away_rest)) in games.iter().zip(rest_days(games)) {
+        let sides = [(home_rest, game.home_goals, game.away_goals), (away_rest, game.away_goals, game.home_goals)];
+        for (rest, scored, conceded) in sides {
+            let Some(rest) = rest else { continue };
+            let band = bands.iter().position(|(_, lo, hi)| rest >= *lo && rest <= *hi).unwrap();
+            totals[band].0 += 1;
+            totals[band].1 += points(scored, conceded);
+        }
+    }
+    let mut result = Vec::new();
+    for ((label, _, _), (appearances, points)) in bands.iter().zip(totals).filter(|(_, (appearances, _))| *appearances > 0) {
+        result.push((label.to_string(), appearances, points as f64 / appearances as f64));
+    }
+    result
+}
+
+pub fn rest_correlation(games: &[Game]) -> f64 {
+    // the Pearson correlation between the home side's rest advantage (home rest days minus away rest days)
+    // and the home side's goal difference, over every game where both teams had a previous game that season.
+    let mut pairs = Vec::new();
+    for (game, rests) in games.iter().zip(rest_days(games)) {
+        if let (Some(home_rest), Some(away_rest)) = rests {
+            pairs.push(((home_rest - away_rest) as f64, game.home_goals as f64 - game.away_goals as f64));
+        }
+    }
+    crate::stats::correlation(&pairs)
+}
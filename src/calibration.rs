@@ -0,0 +1,62 @@
+use serde::Serialize;
+use crate::predict::Prediction;
+
+// a module for checking whether a predictor's probabilities mean what they say: of the games given a 60% chance of a
+// home win, about 60% should end in one. predictions are grouped into equal-width probability bins and each bin's
+// average prediction is compared with how often the result actually happened.
+
+pub const OUTCOMES: [(&str, &str); 3] = [("H", "Home win"), ("D", "Draw"), ("A", "Away win")];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Bin {
+    pub lower: f64,
+    pub upper: f64,
+    pub predictions: usize,
+    pub mean_predicted: f64,
+    pub observed: f64, // the share of the bin's games that ended in the outcome
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Reliability {
+    // one outcome's reliability curve. the expected calibration error is the gap between predicted and observed in each bin,
+    // weighted by how many predictions fell in it: 0 is perfectly calibrated, and 0.05 means five points out on average.
+    pub outcome: String,
+    pub bins: Vec<Bin>, // only the bins with predictions in them
+    pub expected_calibration_error: f64,
+}
+
+pub fn reliability(predictions: &[Prediction], outcome: &str, bins: usize) -> Reliability {
+    // bins the probability given to one outcome, from 0 to 1 in `bins` equal steps. there has to be at least one bin.
+    assert!(bins > 0, "A reliability curve needs at least one bin");
+    let mut totals = vec![(0, 0.0, 0); bins]; // (predictions, sum of probabilities, times it happened) per bin
+    for prediction in predictions {
+        let p = prediction.probabilities.of(outcome);
+        let i = ((p * bins as f64) as usize).min(bins - 1); // a probability of exactly 1 goes in the top bin
+        totals[i].0 += 1;
+        totals[i].1 += p;
+        if prediction.result == outcome {
+            totals[i].2 += 1;
+        }
+    }
+    let width = 1.0 / bins as f64;
+    let bins: Vec<Bin> = totals
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| t.0 > 0)
+        .map(|(i, (count, sum, happened))| Bin {
+            lower: i as f64 * width,
+            upper: (i + 1) as f64 * width,
+            predictions: *count,
+            mean_predicted: sum / *count as f64,
+            observed: *happened as f64 / *count as f64,
+        })
+        .collect();
+    let expected_calibration_error = bins.iter().map(|b| b.predictions as f64 * (b.mean_predicted - b.observed).abs()).sum::<f64>() / predictions.len() as f64;
+    let name = OUTCOMES.iter().find(|o| o.0 == outcome).map(|o| o.1).unwrap_or(outcome);
+    Reliability { outcome: name.to_string(), bins, expected_calibration_error }
+}
+
+pub fn calibration(predictions: &[Prediction], bins: usize) -> Vec<Reliability> {
+    // the reliability curves for a home win, a draw and an away win.
+    OUTCOMES.iter().map(|(outcome, _)| reliability(predictions, outcome, bins)).collect()
+}
@@ -0,0 +1,179 @@
+use std::collections::BTreeMap;
+use serde::Serialize;
+use crate::Game;
+use crate::stats::Rng;
+
+// a module for finding where the home advantage changed level, rather than just which season was lowest.
+// the series is the home-win rate minus the away-win rate, per season or per matchweek. breaks are found by circular
+// binary segmentation: the stretch whose mean differs most from the rest of the series is kept if a permutation test says a
+// difference that big would rarely come from the same values shuffled, and then each piece is searched again. looking for a
+// stretch rather than a single split means a level that drops and later recovers, like the closed-doors seasons, is found.
+
+pub const SIGNIFICANCE: f64 = 0.05;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Point {
+    pub season: usize,
+    pub week: Option<usize>, // None for a whole season
+    pub games: usize,
+    pub value: f64, // home-win rate minus away-win rate, in percentage points
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Changepoint {
+    // a break between two points of the series; the means are of the whole stretches either side, up to the neighbouring breaks.
+    pub season: usize,
+    pub week: Option<usize>, // the first season (or matchweek) after the break
+    pub before_mean: f64,
+    pub after_mean: f64,
+    pub p_value: f64,
+}
+
+impl Changepoint {
+    pub fn describe(&self) -> String {
+        let from = match self.week {
+            Some(week) => format!("week {} of {}", week, self.season),
+            None => self.season.to_string(),
+        };
+        format!("From {} the home-minus-away win rate moved from {:.2} to {:.2} points (p = {:.3})", from, self.before_mean, self.after_mean, self.p_value)
+    }
+}
+
+fn edge(games: &[&Game]) -> f64 {
+    // the home-win rate minus the away-win rate of some games.
+    let home = games.iter().filter(|g| g.result == "H").count() as f64;
+    let away = games.iter().filter(|g| g.result == "A").count() as f64;
+    (home - away) / games.len() as f64 * 100.0
+}
+
+pub fn season_series(games: &[Game], seasons: &[usize]) -> Vec<Point> {
+    // one point per season.
+    seasons
+        .iter()
+        .map(|season| {
+            let season_games: Vec<&Game> = games.iter().filter(|g| g.season == *season).collect();
+            Point { season: *season, week: None, games: season_games.len(), value: edge(&season_games) }
+        })
+        .filter(|p| p.games > 0)
+        .collect()
+}
+
+pub fn matchweek_series(games: &[Game]) -> Vec<Point> {
+    // one point per matchweek of every season, in order. unlike advantage::matchweek_series nothing is smoothed,
+    // since a rolling average makes neighbouring points depend on each other and the permutation test assumes they don't.
+    let mut weeks: BTreeMap<(usize, usize), Vec<&Game>> = BTreeMap::new();
+    for game in games {
+        weeks.entry((game.season, game.week)).or_default().push(game);
+    }
+    weeks
+        .into_iter()
+        .map(|((season, week), week_games)| Point { season, week: Some(week), games: week_games.len(), value: edge(&week_games) })
+        .collect()
+}
+
+fn best_stretch(values: &[f64], min_size: usize) -> Option<(usize, usize, f64)> {
+    // the stretch [start, end) whose mean is furthest from the rest's, as (start, end, statistic). the statistic is the
+    // between-group sum of squares k (n - k) / n × (mean inside - mean outside)², how much of the variation the stretch explains.
+    // a stretch touching either end of the series is a single split. every piece, inside and out, is at least `min_size` long.
+    let n = values.len();
+    if n < 2 * min_size {
+        return None;
+    }
+    let mut prefix = Vec::with_capacity(n + 1);
+    prefix.push(0.0);
+    for value in values {
+        prefix.push(prefix[prefix.len() - 1] + value);
+    }
+    let mean = prefix[n] / n as f64;
+    let weights: Vec<f64> = (0..n).map(|k| if k == 0 { 0.0 } else { n as f64 / (k * (n - k)) as f64 }).collect(); // by stretch length
+    let mut best: Option<(usize, usize, f64)> = None;
+    let mut best_statistic = -1.0;
+    for start in 0..=n - min_size {
+        if start > 0 && start < min_size {
+            continue;
+        }
+        let last = if start == 0 { n - min_size } else { n }; // a stretch from the start must leave at least min_size after it
+        let from = prefix[start];
+        for (end, to) in prefix.iter().enumerate().take(last + 1).skip(start + min_size) {
+            if end < n && n - end < min_size {
+                continue; // too little left after the stretch, though running right to the end is fine
+            }
+            let inside = end - start;
+            let excess = to - from - inside as f64 * mean; // inside - outside = excess n / (k (n - k))
+            let statistic = excess * excess * weights[inside];
+            if statistic > best_statistic {
+                best_statistic = statistic;
+                best = Some((start, end, statistic));
+            }
+        }
+    }
+    best
+}
+
+fn permutation_p_value(values: &[f64], statistic: f64, min_size: usize, permutations: usize, rng: &mut Rng) -> f64 {
+    // the share of shuffles of the values whose best stretch is at least as good, counting the real order as one of them.
+    // stops as soon as the p-value can't come in under SIGNIFICANCE, since the exact value of an insignificant one isn't reported.
+    let mut shuffled = values.to_vec();
+    let mut as_good = 0;
+    let give_up = (SIGNIFICANCE * (permutations + 1) as f64) as usize;
+    for done in 1..=permutations {
+        for i in (1..shuffled.len()).rev() {
+            shuffled.swap(i, rng.below(i + 1));
+        }
+        if best_stretch(&shuffled, min_size).is_some_and(|b| b.2 >= statistic) {
+            as_good += 1;
+            if as_good >= give_up {
+                return (as_good + 1) as f64 / (done + 1) as f64;
+            }
+        }
+    }
+    (as_good + 1) as f64 / (permutations + 1) as f64
+}
+
+fn segment(values: &[f64], offset: usize, min_size: usize, permutations: usize, rng: &mut Rng, breaks: &mut Vec<(usize, f64)>) {
+    // finds a significant stretch in the piece, if there is one, and looks for more in each of the (up to three) pieces it leaves.
+    let Some((start, end, statistic)) = best_stretch(values, min_size) else {
+        return;
+    };
+    let p_value = permutation_p_value(values, statistic, min_size, permutations, rng);
+    if p_value >= SIGNIFICANCE {
+        return;
+    }
+    let mut cuts = vec![0];
+    for cut in [start, end] {
+        if cut > 0 && cut < values.len() {
+            breaks.push((offset + cut, p_value));
+            cuts.push(cut);
+        }
+    }
+    cuts.push(values.len());
+    for piece in cuts.windows(2) {
+        segment(&values[piece[0]..piece[1]], offset + piece[0], min_size, permutations, rng, breaks);
+    }
+}
+
+pub fn changepoints(series: &[Point], min_size: usize, permutations: usize) -> Vec<Changepoint> {
+    // the significant breaks in the series in order, where every stretch between breaks is at least `min_size` points long.
+    // each test shuffles the values `permutations` times, so the smallest p-value it can give is 1 / (permutations + 1); the
+    // scan takes time proportional to the square of the series' length, so long series are worth fewer shuffles.
+    // the permutations are seeded, so the same series always gives the same breaks.
+    let values: Vec<f64> = series.iter().map(|p| p.value).collect();
+    let mut breaks = Vec::new();
+    segment(&values, 0, min_size.max(1), permutations, &mut Rng::new(values.len() as u64), &mut breaks);
+    breaks.sort_by_key(|b| b.0);
+    let mut bounds: Vec<usize> = vec![0];
+    bounds.extend(breaks.iter().map(|b| b.0));
+    bounds.push(values.len());
+    let mean = |from: usize, to: usize| values[from..to].iter().sum::<f64>() / (to - from) as f64;
+    breaks
+        .iter()
+        .enumerate()
+        .map(|(i, (index, p_value))| Changepoint {
+            season: series[*index].season,
+            week: series[*index].week,
+            before_mean: mean(bounds[i], bounds[i + 1]),
+            after_mean: mean(bounds[i + 1], bounds[i + 2]),
+            p_value: *p_value,
+        })
+        .collect()
+}
@@ -0,0 +1,334 @@
+use std::error::Error;
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use crate::Game;
+use crate::wins::{away_estimate, away_pct, draw_estimate, draw_pct, home_estimate, home_pct};
+use crate::stats::Estimate;
+use std::collections::HashMap;
+use crate::advantage::{home_edge_trend, MatchweekPoint, CLOSED_DOORS, LAST_CLOSED_DOORS_SEASON};
+use crate::calibration::Reliability;
+use crate::compare::season_points_per_game;
+
+// a module for the charts. each chart draws onto a drawing area it is given, so the same chart can be written to a PNG
+// with BitMapBackend or embedded in the HTML report with SVGBackend.
+
+fn error_band(estimates: &[(usize, Estimate)], colour: RGBColor) -> Polygon<(f64, f64)> {
+    // a shaded band from each season's lower bound to its upper bound, drawn under the line it belongs to.
+    let lower = estimates.iter().map(|(s, e)| (*s as f64, e.lower));
+    let upper = estimates.iter().rev().map(|(s, e)| (*s as f64, e.upper));
+    Polygon::new(lower.chain(upper).collect::<Vec<_>>(), colour.mix(0.15).filled())
+}
+
+pub fn result_rates_chart<DB: DrawingBackend>(drawing_area: &DrawingArea<DB, Shift>, games: &[Game], seasons: &[usize]) -> Result<(), Box<dyn Error>>
+where DB::ErrorType: 'static {
+    // plots the home win, away win and draw rates for every season.
+    let (Some(&first), Some(&last)) = (seasons.first(), seasons.last()) else {
+        return Err(Box::new(crate::game::MyError("No seasons to plot".to_string())));
+    };
+    drawing_area.fill(&WHITE)?;
+    let mut chart_builder = ChartBuilder::on(drawing_area)
+        .x_label_area_size(35)
+        .y_label_area_size(50)
+        .caption(format!("Home, Away, and Draw results - {}-{}", first, last), ("sans-serif", 20).into_font())
+        .margin(5)
+        .build_cartesian_2d(first as f64..(last as f64).max(first as f64 + 1.0), 10.0..60.0)?;
+    chart_builder.configure_mesh()
+        .y_desc("Result rates")
+        .x_desc("Season")
+        .axis_desc_style(("sans-serif", 15))
+        .draw()?;
+    for (estimate, colour) in [(home_estimate as fn(&[Game], usize) -> Estimate, BLACK), (away_estimate, RED), (draw_estimate, BLUE)] {
+        let estimates: Vec<(usize, Estimate)> = seasons.iter().map(|s| (*s, estimate(games, *s))).collect();
+        chart_builder.draw_series(std::iter::once(error_band(&estimates, colour)))?; // the 95% Wilson interval around each line
+    }
+    chart_builder.draw_series(LineSeries::new(seasons.iter().map(|s| (*s as f64, home_pct(games, *s))), BLACK))? // for each season, compute the home win rate
+        .label("Home win rate")
+        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], BLACK)); // add legend
+    chart_builder.draw_series(LineSeries::new(seasons.iter().map(|s| (*s as f64, away_pct(games, *s))), RED))? // compute home, win, and away rate separately
+        .label("Away win rate")
+        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], RED));
+    chart_builder.draw_series(LineSeries::new(seasons.iter().map(|s| (*s as f64, draw_pct(games, *s))), BLUE))?
+        .label("Draw rate")
+        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], BLUE));
+    chart_builder.configure_series_labels()
+        .position(SeriesLabelPosition::LowerRight)
+        .border_style(BLACK)
+        .background_style(WHITE.mix(0.8))
+        .draw()?;
+    drawing_area.present()?;
+    Ok(())
+}
+
+pub fn goal_averages_chart<DB: DrawingBackend>(drawing_area: &DrawingArea<DB, Shift>, games: &[Game], seasons: &[usize]) -> Result<(), Box<dyn Error>>
+where DB::ErrorType: 'static {
+    // plots average goal trends year over year.
+    let (Some(&first), Some(&last)) = (seasons.first(), seasons.last()) else {
+        return Err(Box::new(crate::game::MyError("No seasons to plot".to_string())));
+    };
+    drawing_area.fill(&WHITE)?;
+    let mut chart_builder = ChartBuilder::on(drawing_area)
+        .x_label_area_size(35)
+        .y_label_area_size(50)
+        .caption(format!("Total goals per season - {}-{}", first, last), ("sans-serif", 20).into_font())
+        .margin(5)
+        .build_cartesian_2d(first as f64..(last as f64).max(first as f64 + 1.0), 2.0..3.1)?;
+    chart_builder.configure_mesh()
+        .y_desc("Average goals per game")
+        .x_desc("Season")
+        .axis_desc_style(("sans-serif", 15))
+        .draw()?;
+    let estimates: Vec<(usize, Estimate)> = seasons.iter().map(|s| (*s, crate::goal_avg_estimate(games, *s))).collect();
+    chart_builder.draw_series(std::iter::once(error_band(&estimates, MAGENTA)))?; // the 95% bootstrap interval
+    chart_builder.draw_series(LineSeries::new(seasons.iter().map(|s| (*s as f64, crate::goal_avg(games, *s))), MAGENTA))?
+        .label("Average goals per game")
+        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], MAGENTA));
+    chart_builder.configure_series_labels()
+        .position(SeriesLabelPosition::LowerRight)
+        .border_style(BLACK)
+        .background_style(WHITE.mix(0.8))
+        .draw()?;
+    drawing_area.present()?;
+    Ok(())
+}
+
+pub fn home_edge_chart<DB: DrawingBackend>(drawing_area: &DrawingArea<DB, Shift>, games: &[Game], team: Option<&String>, seasons: &[usize]) -> Result<(), Box<dyn Error>>
+where DB::ErrorType: 'static {
+    // plots home minus away points per game for each season, for one team or for the whole league (team = None).
+    // the closed-doors seasons are shaded grey, and seasons the team spent outside the league leave a gap in the line.
+    let trend = home_edge_trend(games, team, seasons);
+    if trend.is_empty() {
+        return Err(Box::new(crate::game::MyError("No seasons to plot".to_string())));
+    }
+    let first = *seasons.first().unwrap() as f64;
+    let last = *seasons.last().unwrap() as f64;
+    let mut y_min = trend.iter().map(|(_, e)| *e).fold(0.0, f64::min);
+    let mut y_max = trend.iter().map(|(_, e)| *e).fold(0.0, f64::max);
+    y_min = (y_min * 4.0).floor() / 4.0; // round the axis out to the nearest quarter point
+    y_max = (y_max * 4.0).ceil() / 4.0;
+    let name = match team {
+        Some(t) => t.to_string(),
+        None => "All teams".to_string(),
+    };
+
+    drawing_area.fill(&WHITE)?;
+    let mut chart_builder = ChartBuilder::on(drawing_area)
+        .x_label_area_size(35)
+        .y_label_area_size(50)
+        .caption(format!("Home minus away points per game - {}", name), ("sans-serif", 20).into_font())
+        .margin(5)
+        .build_cartesian_2d(first..last.max(first + 1.0), y_min..y_max)?;
+    chart_builder.configure_mesh()
+        .y_desc("Home PPG - away PPG")
+        .x_desc("Season")
+        .axis_desc_style(("sans-serif", 15))
+        .draw()?;
+    let shade = RGBColor(200, 200, 200).mix(0.5);
+    let closed_start = CLOSED_DOORS[0] as f64 - 0.5;
+    let closed_end = LAST_CLOSED_DOORS_SEASON as f64 + 0.5;
+    chart_builder.draw_series(std::iter::once(Rectangle::new([(closed_start, y_min), (closed_end, y_max)], shade.filled())))?
+        .label("Closed doors")
+        .legend(move |(x, y)| Rectangle::new([(x, y - 5), (x + 20, y + 5)], shade.filled()));
+    chart_builder.draw_series(LineSeries::new(vec![(first, 0.0), (last, 0.0)], BLACK.mix(0.3)))?; // no home advantage
+
+    for (i, run) in runs(trend).into_iter().enumerate() { // split the line wherever a season is missing
+        let series = chart_builder.draw_series(LineSeries::new(run, RED).point_size(2))?;
+        if i == 0 {
+            series
+                .label("Home edge")
+                .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], RED));
+        }
+    }
+    chart_builder.configure_series_labels()
+        .position(SeriesLabelPosition::LowerRight)
+        .border_style(BLACK)
+        .background_style(WHITE.mix(0.8))
+        .draw()?;
+    drawing_area.present()?;
+    Ok(())
+}
+
+fn matchweek_x(season: usize, week: usize, season_weeks: &HashMap<usize, usize>) -> f64 {
+    // places a matchweek on a continuous season axis: the season ending in 2021 covers 2020.0 to 2021.0.
+    (season - 1) as f64 + week as f64 / season_weeks[&season] as f64
+}
+
+pub fn matchweek_chart(series: &[MatchweekPoint], window: usize, closed_from: Option<(usize, usize)>, path: &str) -> Result<(), Box<dyn Error>> {
+    // draws the rolling home-win rate (top) and home goal difference (bottom) for every matchweek in the series.
+    // the closed-doors period is shaded from `closed_from`, the first matchweek played without fans, to the end of 2020-21.
+    if series.is_empty() {
+        return Err(Box::new(crate::game::MyError("No matchweeks to plot".to_string())));
+    }
+    let mut season_weeks: HashMap<usize, usize> = HashMap::new(); // the number of matchweeks in each season, which changed when the league went from 22 to 20 teams
+    for point in series {
+        let weeks = season_weeks.entry(point.season).or_insert(0);
+        *weeks = (*weeks).max(point.week);
+    }
+    let first = (series[0].season - 1) as f64;
+    let last = series[series.len() - 1].season as f64;
+    let closed = closed_from.map(|(season, week)| (matchweek_x(season, week - 1, &season_weeks), LAST_CLOSED_DOORS_SEASON as f64)); // from the end of the week before
+    let shade = RGBColor(200, 200, 200).mix(0.5);
+
+    let drawing_area = BitMapBackend::new(path, (1280, 720)).into_drawing_area();
+    drawing_area.fill(&WHITE)?;
+    let drawing_area = drawing_area.titled(&format!("Home advantage by matchweek - rolling {} matchweeks", window), ("sans-serif", 20).into_font())?;
+    let panels = drawing_area.split_evenly((2, 1));
+    let metrics = [
+        ("Home win rate", series.iter().map(|p| p.home_win_rate).collect::<Vec<f64>>(), BLACK),
+        ("Home goal difference", series.iter().map(|p| p.home_goal_diff).collect::<Vec<f64>>(), RED),
+    ];
+    for (panel, (desc, values, colour)) in panels.iter().zip(metrics) {
+        let y_min = values.iter().cloned().fold(f64::MAX, f64::min).min(0.0);
+        let y_max = values.iter().cloned().fold(f64::MIN, f64::max) * 1.05;
+        let mut chart_builder = ChartBuilder::on(panel)
+            .x_label_area_size(35)
+            .y_label_area_size(50)
+            .margin(5)
+            .build_cartesian_2d(first..last, y_min..y_max)?;
+        chart_builder.configure_mesh()
+            .y_desc(desc)
+            .x_desc("Year")
+            .axis_desc_style(("sans-serif", 15))
+            .draw()?;
+        if let Some((closed_start, closed_end)) = closed {
+            chart_builder.draw_series(std::iter::once(Rectangle::new([(closed_start, y_min), (closed_end, y_max)], shade.filled())))?
+                .label("Closed doors")
+                .legend(move |(x, y)| Rectangle::new([(x, y - 5), (x + 20, y + 5)], shade.filled()));
+        }
+        chart_builder.draw_series(LineSeries::new(series.iter().zip(values).map(|(p, v)| (matchweek_x(p.season, p.week, &season_weeks), v)), colour))?
+            .label(desc)
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], colour));
+        chart_builder.configure_series_labels()
+            .position(SeriesLabelPosition::LowerLeft)
+            .border_style(BLACK)
+            .background_style(WHITE.mix(0.8))
+            .draw()?;
+    }
+    drawing_area.present()?;
+    Ok(())
+}
+
+pub fn reliability_chart<DB: DrawingBackend>(drawing_area: &DrawingArea<DB, Shift>, name: &str, curves: &[Reliability]) -> Result<(), Box<dyn Error>>
+where DB::ErrorType: 'static {
+    // plots the observed frequency against the predicted probability for each outcome, with the diagonal a perfectly calibrated predictor would follow.
+    drawing_area.fill(&WHITE)?;
+    let mut chart_builder = ChartBuilder::on(drawing_area)
+        .x_label_area_size(35)
+        .y_label_area_size(50)
+        .caption(format!("Calibration - {}", name), ("sans-serif", 20).into_font())
+        .margin(5)
+        .build_cartesian_2d(0.0..1.0, 0.0..1.0)?;
+    chart_builder.configure_mesh()
+        .y_desc("Observed frequency")
+        .x_desc("Predicted probability")
+        .axis_desc_style(("sans-serif", 15))
+        .draw()?;
+    chart_builder.draw_series(LineSeries::new(vec![(0.0, 0.0), (1.0, 1.0)], BLACK.mix(0.3)))?; // perfect calibration
+    for (curve, colour) in curves.iter().zip([BLACK, BLUE, RED]) {
+        chart_builder.draw_series(LineSeries::new(curve.bins.iter().map(|b| (b.mean_predicted, b.observed)), colour).point_size(2))?
+            .label(format!("{} (ECE {:.3})", curve.outcome, curve.expected_calibration_error))
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], colour));
+    }
+    chart_builder.configure_series_labels()
+        .position(SeriesLabelPosition::UpperLeft)
+        .border_style(BLACK)
+        .background_style(WHITE.mix(0.8))
+        .draw()?;
+    drawing_area.present()?;
+    Ok(())
+}
+
+fn runs(points: Vec<(usize, f64)>) -> Vec<Vec<(f64, f64)>> {
+    // splits a line wherever the x values skip one, such as a season a team spent outside the league.
+    let mut runs: Vec<Vec<(f64, f64)>> = Vec::new();
+    let mut previous = 0;
+    for (x, y) in points {
+        if runs.is_empty() || x != previous + 1 {
+            runs.push(Vec::new());
+        }
+        runs.last_mut().unwrap().push((x as f64, y));
+        previous = x;
+    }
+    runs
+}
+
+pub fn comparison_chart<DB: DrawingBackend>(drawing_area: &DrawingArea<DB, Shift>, games: &[Game], teams: &[String], seasons: &[usize]) -> Result<(), Box<dyn Error>>
+where DB::ErrorType: 'static {
+    // plots each team's points per game season by season, one line per team, with a gap for any season a team spent outside the league.
+    let (Some(&first), Some(&last)) = (seasons.first(), seasons.last()) else {
+        return Err(Box::new(crate::game::MyError("No seasons to plot".to_string())));
+    };
+    let (first, last) = (first as f64, last as f64);
+    drawing_area.fill(&WHITE)?;
+    let mut chart_builder = ChartBuilder::on(drawing_area)
+        .x_label_area_size(35)
+        .y_label_area_size(50)
+        .caption(format!("Points per game - {}", teams.join(", ")), ("sans-serif", 20).into_font())
+        .margin(5)
+        .build_cartesian_2d(first..last.max(first + 1.0), 0.0..3.0)?;
+    chart_builder.configure_mesh()
+        .y_desc("Points per game")
+        .x_desc("Season")
+        .axis_desc_style(("sans-serif", 15))
+        .draw()?;
+    for (i, team) in teams.iter().enumerate() {
+        let colour = Palette99::pick(i).to_rgba();
+        for (j, run) in runs(season_points_per_game(games, team, seasons)).into_iter().enumerate() {
+            let series = chart_builder.draw_series(LineSeries::new(run, colour.stroke_width(2)).point_size(3))?;
+            if j == 0 {
+                series
+                    .label(team)
+                    .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], colour.stroke_width(2)));
+            }
+        }
+    }
+    chart_builder.configure_series_labels()
+        .position(SeriesLabelPosition::LowerRight)
+        .border_style(BLACK)
+        .background_style(WHITE.mix(0.8))
+        .draw()?;
+    drawing_area.present()?;
+    Ok(())
+}
+
+pub fn bump_chart<DB: DrawingBackend>(drawing_area: &DrawingArea<DB, Shift>, title: &str, x_desc: &str, lines: &[(String, Vec<(usize, usize)>)], places: usize) -> Result<(), Box<dyn Error>>
+where DB::ErrorType: 'static {
+    // plots league positions over time, one line per team, with first place at the top. each line is a team's (x, position) points,
+    // where x is a season or a matchweek; a gap in x (a season spent outside the league) breaks the line.
+    let xs: Vec<usize> = lines.iter().flat_map(|(_, points)| points.iter().map(|p| p.0)).collect();
+    let (Some(first), Some(last)) = (xs.iter().min(), xs.iter().max()) else {
+        return Err(Box::new(crate::game::MyError("No positions to plot".to_string())));
+    };
+    drawing_area.fill(&WHITE)?;
+    let mut chart_builder = ChartBuilder::on(drawing_area)
+        .x_label_area_size(35)
+        .y_label_area_size(50)
+        .caption(title, ("sans-serif", 20).into_font())
+        .margin(5)
+        .build_cartesian_2d(*first as f64 - 0.5..*last as f64 + 0.5, -(places as f64) - 0.5..-0.5)?; // positions are drawn negated, so 1st is at the top
+    chart_builder.configure_mesh()
+        .y_desc("League position")
+        .x_desc(x_desc)
+        .y_labels(places)
+        .y_label_formatter(&|y| format!("{}", -y.round() as i64))
+        .x_label_formatter(&|x| format!("{}", x.round() as i64))
+        .axis_desc_style(("sans-serif", 15))
+        .draw()?;
+    for (i, (team, points)) in lines.iter().enumerate() {
+        let colour = Palette99::pick(i).to_rgba();
+        for (j, run) in runs(points.iter().map(|(x, position)| (*x, -(*position as f64))).collect()).into_iter().enumerate() {
+            let series = chart_builder.draw_series(LineSeries::new(run, colour.stroke_width(2)).point_size(3))?;
+            if j == 0 {
+                series
+                    .label(team)
+                    .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], colour.stroke_width(2)));
+            }
+        }
+    }
+    chart_builder.configure_series_labels()
+        .position(SeriesLabelPosition::LowerRight)
+        .border_style(BLACK)
+        .background_style(WHITE.mix(0.8))
+        .draw()?;
+    drawing_area.present()?;
+    Ok(())
+}
@@ -0,0 +1,177 @@
+use std::error::Error;
+use std::fs::File;
+use std::sync::Arc;
+use arrow::array::{Array, ArrayRef, AsArray, Date32Array, RecordBatch, StringArray, UInt16Array, UInt32Array, UInt8Array};
+use arrow::compute::{cast_with_options, CastOptions};
+use arrow::datatypes::{DataType, Date32Type, Field, Schema, SchemaRef, UInt16Type, UInt32Type, UInt8Type};
+use arrow::ipc::reader::FileReader;
+use arrow::ipc::writer::FileWriter;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use crate::Game;
+use crate::game::{MatchStats, MyError};
+
+// a module that converts games to and from Apache Arrow record batches, and writes them as Parquet or Arrow IPC files,
+// so tools like Polars and pandas get typed columns rather than parsing the csv again.
+// every column a Game has is in the schema; the half-time score and extended stats are nullable.
+
+const STAT_COLUMNS: [&str; 10] = ["home_shots", "away_shots", "home_shots_on_target", "away_shots_on_target", "home_corners", "away_corners", "home_yellows", "away_yellows", "home_reds", "away_reds"];
+
+pub fn schema() -> SchemaRef {
+    // the typed layout of the files. counts are the smallest unsigned type that fits them, and the date is a calendar date.
+    let mut fields = vec![
+        Field::new("league", DataType::Utf8, false),
+        Field::new("season", DataType::UInt16, false),
+        Field::new("week", DataType::UInt8, false),
+        Field::new("date", DataType::Date32, false),
+        Field::new("home", DataType::Utf8, false),
+        Field::new("away", DataType::Utf8, false),
+        Field::new("home_goals", DataType::UInt8, false),
+        Field::new("away_goals", DataType::UInt8, false),
+        Field::new("result", DataType::Utf8, false),
+        Field::new("ht_home_goals", DataType::UInt8, true),
+        Field::new("ht_away_goals", DataType::UInt8, true),
+    ];
+    for name in STAT_COLUMNS {
+        fields.push(Field::new(name, DataType::UInt8, true));
+    }
+    fields.push(Field::new("referee", DataType::Utf8, true));
+    fields.push(Field::new("attendance", DataType::UInt32, true));
+    Arc::new(Schema::new(fields))
+}
+
+fn stat_values(stats: &MatchStats) -> [Option<usize>; 10] {
+    // the stats in STAT_COLUMNS order.
+    [stats.home_shots, stats.away_shots, stats.home_shots_on_target, stats.away_shots_on_target, stats.home_corners, stats.away_corners, stats.home_yellows, stats.away_yellows, stats.home_reds, stats.away_reds]
+}
+
+pub fn to_record_batch(games: &[Game]) -> Result<RecordBatch, Box<dyn Error>> {
+    // one column per field, in schema order.
+    let small = |n: usize| u8::try_from(n).map_err(|_| MyError(format!("{} is too large for its column", n)));
+    let season = |n: usize| u16::try_from(n).map_err(|_| MyError(format!("{} is too large for the season column", n)));
+    let crowd = |n: usize| u32::try_from(n).map_err(|_| MyError(format!("{} is too large for the attendance column", n)));
+    let stats: Vec<MatchStats> = games.iter().map(|g| g.stats.clone().unwrap_or_default()).collect();
+    let mut columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from_iter_values(games.iter().map(|g| &g.competition))),
+        Arc::new(UInt16Array::from(games.iter().map(|g| season(g.season)).collect::<Result<Vec<u16>, _>>()?)),
+        Arc::new(UInt8Array::from(games.iter().map(|g| small(g.week)).collect::<Result<Vec<u8>, _>>()?)),
+        Arc::new(Date32Array::from(games.iter().map(|g| Date32Type::from_naive_date(g.date)).collect::<Vec<i32>>())),
+        Arc::new(StringArray::from_iter_values(games.iter().map(|g| &g.home))),
+        Arc::new(StringArray::from_iter_values(games.iter().map(|g| &g.away))),
+        Arc::new(UInt8Array::from(games.iter().map(|g| small(g.home_goals)).collect::<Result<Vec<u8>, _>>()?)),
+        Arc::new(UInt8Array::from(games.iter().map(|g| small(g.away_goals)).collect::<Result<Vec<u8>, _>>()?)),
+        Arc::new(StringArray::from_iter_values(games.iter().map(|g| &g.result))),
+        Arc::new(games.iter().map(|g| g.ht_home_goals.map(small).transpose()).collect::<Result<UInt8Array, _>>()?),
+        Arc::new(games.iter().map(|g| g.ht_away_goals.map(small).transpose()).collect::<Result<UInt8Array, _>>()?),
+    ];
+    for i in 0..STAT_COLUMNS.len() {
+        columns.push(Arc::new(stats.iter().map(|s| stat_values(s)[i].map(small).transpose()).collect::<Result<UInt8Array, _>>()?));
+    }
+    columns.push(Arc::new(stats.iter().map(|s| s.referee.clone()).collect::<StringArray>()));
+    columns.push(Arc::new(stats.iter().map(|s| s.attendance.map(crowd).transpose()).collect::<Result<UInt32Array, _>>()?));
+    Ok(RecordBatch::try_new(schema(), columns)?)
+}
+
+fn column(batch: &RecordBatch, name: &str) -> Result<Option<ArrayRef>, Box<dyn Error>> {
+    // a column cast to the type the schema gives it, so files written by other tools with wider types (like Polars' i64) still read.
+    // None for an optional column the file doesn't have. a value that doesn't fit the schema's type is an error rather than
+    // a null, and so is a null in a column that isn't nullable.
+    let schema = schema();
+    let field = schema.field_with_name(name)?;
+    let options = CastOptions { safe: false, ..Default::default() };
+    match batch.column_by_name(name) {
+        Some(array) if !field.is_nullable() && array.null_count() > 0 => Err(MyError(format!("The {} column has missing values", name)).into()),
+        Some(array) => match cast_with_options(array, field.data_type(), &options) {
+            Ok(array) => Ok(Some(array)),
+            Err(e) => Err(MyError(format!("The {} column has a value that doesn't fit: {}", name, e)).into()),
+        },
+        None if field.is_nullable() => Ok(None),
+        None => Err(MyError(format!("The file has no {} column", name)).into()),
+    }
+}
+
+pub fn from_record_batch(batch: &RecordBatch) -> Result<Vec<Game>, Box<dyn Error>> {
+    // turns a batch back into Games.
+    let required = |name: &str| -> Result<ArrayRef, Box<dyn Error>> { Ok(column(batch, name)?.unwrap()) };
+    let optional_u8 = |array: &Option<ArrayRef>, row: usize| -> Option<usize> {
+        let values = array.as_ref()?.as_primitive::<UInt8Type>();
+        if values.is_null(row) { None } else { Some(values.value(row) as usize) }
+    };
+    let (league, season, week, date) = (required("league")?, required("season")?, required("week")?, required("date")?);
+    let (home, away, home_goals, away_goals, result) = (required("home")?, required("away")?, required("home_goals")?, required("away_goals")?, required("result")?);
+    let (ht_home, ht_away) = (column(batch, "ht_home_goals")?, column(batch, "ht_away_goals")?);
+    let stat_columns = STAT_COLUMNS.iter().map(|name| column(batch, name)).collect::<Result<Vec<_>, _>>()?;
+    let (referee, attendance) = (column(batch, "referee")?, column(batch, "attendance")?);
+    let mut games = Vec::with_capacity(batch.num_rows());
+    for row in 0..batch.num_rows() {
+        let stat = |i: usize| optional_u8(&stat_columns[i], row);
+        let stats = MatchStats {
+            home_shots: stat(0),
+            away_shots: stat(1),
+            home_shots_on_target: stat(2),
+            away_shots_on_target: stat(3),
+            home_corners: stat(4),
+            away_corners: stat(5),
+            home_yellows: stat(6),
+            away_yellows: stat(7),
+            home_reds: stat(8),
+            away_reds: stat(9),
+            referee: referee.as_ref().map(|a| a.as_string::<i32>()).filter(|a| !a.is_null(row)).map(|a| a.value(row).to_string()),
+            attendance: attendance.as_ref().map(|a| a.as_primitive::<UInt32Type>()).filter(|a| !a.is_null(row)).map(|a| a.value(row) as usize),
+        };
+        let date_value = date.as_primitive::<Date32Type>().value(row);
+        let result = result.as_string::<i32>().value(row);
+        games.push(Game {
+            season: season.as_primitive::<UInt16Type>().value(row) as usize,
+            week: week.as_primitive::<UInt8Type>().value(row) as usize,
+            date: Date32Type::to_naive_date_opt(date_value).ok_or_else(|| MyError(format!("Bad date {} in the file", date_value)))?,
+            home: home.as_string::<i32>().value(row).to_string(),
+            away: away.as_string::<i32>().value(row).to_string(),
+            home_goals: home_goals.as_primitive::<UInt8Type>().value(row) as usize,
+            away_goals: away_goals.as_primitive::<UInt8Type>().value(row) as usize,
+            result: result.to_string(),
+            competition: league.as_string::<i32>().value(row).to_string(),
+            ht_home_goals: optional_u8(&ht_home, row),
+            ht_away_goals: optional_u8(&ht_away, row),
+            stats: if stats == MatchStats::default() { None } else { Some(stats) },
+        });
+    }
+    Ok(games)
+}
+
+pub fn write_parquet(games: &[Game], path: &str) -> Result<(), Box<dyn Error>> {
+    // snappy-compressed, which every Parquet reader supports.
+    let properties = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+    let mut writer = ArrowWriter::try_new(File::create(path)?, schema(), Some(properties))?;
+    writer.write(&to_record_batch(games)?)?;
+    writer.close()?;
+    Ok(())
+}
+
+pub fn read_parquet(path: &str) -> Result<Vec<Game>, Box<dyn Error>> {
+    let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(path)?)?.build()?;
+    let mut games = Vec::new();
+    for batch in reader {
+        games.extend(from_record_batch(&batch?)?);
+    }
+    Ok(games)
+}
+
+pub fn write_ipc(games: &[Game], path: &str) -> Result<(), Box<dyn Error>> {
+    // an Arrow IPC file, which Polars and pyarrow can memory-map without decoding (also known as Feather v2).
+    let mut writer = FileWriter::try_new(File::create(path)?, &schema())?;
+    writer.write(&to_record_batch(games)?)?;
+    writer.finish()?;
+    Ok(())
+}
+
+pub fn read_ipc(path: &str) -> Result<Vec<Game>, Box<dyn Error>> {
+    let reader = FileReader::try_new(File::open(path)?, None)?;
+    let mut games = Vec::new();
+    for batch in reader {
+        games.extend(from_record_batch(&batch?)?);
+    }
+    Ok(games)
+}
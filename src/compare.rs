@@ -0,0 +1,128 @@
+use serde::Serialize;
+use crate::Game;
+use crate::advantage::points;
+use crate::standings::league_table;
+
+// a module for putting several teams side by side over the same range of seasons, where user_choice looks at one.
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TeamComparison {
+    pub team: String,
+    pub seasons: usize, // seasons in the league within the range
+    pub played: usize,
+    pub win_rate: f64,
+    pub points_per_game: f64,
+    pub goals_for_per_game: f64,
+    pub goals_against_per_game: f64,
+    pub home_points_per_game: f64,
+    pub away_points_per_game: f64,
+    pub best_finish: Option<usize>,
+    pub worst_finish: Option<usize>,
+    pub average_finish: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HeadToHead {
+    // the record of `team` against `opponent` in their meetings within the range.
+    pub team: String,
+    pub opponent: String,
+    pub played: usize,
+    pub won: usize,
+    pub drawn: usize,
+    pub lost: usize,
+    pub goals_for: usize,
+    pub goals_against: usize,
+}
+
+pub fn finishes(games: &[Game], team: &String, seasons: &[usize]) -> Vec<(usize, usize)> {
+    // the team's final league position in each season of the range it played in, as (season, position).
+    seasons
+        .iter()
+        .filter_map(|season| league_table(games, *season).iter().position(|r| r.team == *team).map(|i| (*season, i + 1)))
+        .collect()
+}
+
+pub fn season_points_per_game(games: &[Game], team: &String, seasons: &[usize]) -> Vec<(usize, f64)> {
+    // the team's points per game in each season of the range it played in.
+    seasons
+        .iter()
+        .filter_map(|season| {
+            let results: Vec<usize> = games
+                .iter()
+                .filter(|g| g.season == *season)
+                .filter_map(|g| if g.home == *team { Some(points(g.home_goals, g.away_goals)) } else if g.away == *team { Some(points(g.away_goals, g.home_goals)) } else { None })
+                .collect();
+            if results.is_empty() { None } else { Some((*season, results.iter().sum::<usize>() as f64 / results.len() as f64)) }
+        })
+        .collect()
+}
+
+pub fn compare(games: &[Game], teams: &[String], seasons: &[usize]) -> Vec<TeamComparison> {
+    // one row per team, in the order given.
+    teams
+        .iter()
+        .map(|team| {
+            let mut played = 0;
+            let mut won = 0;
+            let mut points_won = 0;
+            let mut goals_for = 0;
+            let mut goals_against = 0;
+            let mut venues = [(0, 0), (0, 0)]; // (points, games) at home and away
+            for game in games.iter().filter(|g| seasons.contains(&g.season)) {
+                let (scored, conceded, venue) = if game.home == *team {
+                    (game.home_goals, game.away_goals, 0)
+                } else if game.away == *team {
+                    (game.away_goals, game.home_goals, 1)
+                } else {
+                    continue;
+                };
+                played += 1;
+                won += (scored > conceded) as usize;
+                points_won += points(scored, conceded);
+                goals_for += scored;
+                goals_against += conceded;
+                venues[venue].0 += points(scored, conceded);
+                venues[venue].1 += 1;
+            }
+            let per_game = |total: usize, count: usize| if count == 0 { 0.0 } else { total as f64 / count as f64 };
+            let positions: Vec<usize> = finishes(games, team, seasons).into_iter().map(|(_, p)| p).collect();
+            TeamComparison {
+                team: team.clone(),
+                seasons: positions.len(),
+                played,
+                win_rate: per_game(won, played) * 100.0,
+                points_per_game: per_game(points_won, played),
+                goals_for_per_game: per_game(goals_for, played),
+                goals_against_per_game: per_game(goals_against, played),
+                home_points_per_game: per_game(venues[0].0, venues[0].1),
+                away_points_per_game: per_game(venues[1].0, venues[1].1),
+                best_finish: positions.iter().copied().min(),
+                worst_finish: positions.iter().copied().max(),
+                average_finish: if positions.is_empty() { None } else { Some(positions.iter().sum::<usize>() as f64 / positions.len() as f64) },
+            }
+        })
+        .collect()
+}
+
+pub fn head_to_heads(games: &[Game], teams: &[String], seasons: &[usize]) -> Vec<HeadToHead> {
+    // every pair's record, from the point of view of the team listed first.
+    let mut records = Vec::new();
+    for (i, team) in teams.iter().enumerate() {
+        for opponent in &teams[i + 1..] {
+            let mut record = HeadToHead { team: team.clone(), opponent: opponent.clone(), played: 0, won: 0, drawn: 0, lost: 0, goals_for: 0, goals_against: 0 };
+            for game in crate::head_to_head(games, team, opponent, seasons) {
+                let (scored, conceded) = if game.home == *team { (game.home_goals, game.away_goals) } else { (game.away_goals, game.home_goals) };
+                record.played += 1;
+                match scored.cmp(&conceded) {
+                    std::cmp::Ordering::Greater => record.won += 1,
+                    std::cmp::Ordering::Equal => record.drawn += 1,
+                    std::cmp::Ordering::Less => record.lost += 1,
+                }
+                record.goals_for += scored;
+                record.goals_against += conceded;
+            }
+            records.push(record);
+        }
+    }
+    records
+}
@@ -0,0 +1,149 @@
+use std::error::Error;
+use chrono::NaiveDate;
+use rusqlite::{params, Connection};
+use crate::Game;
+use crate::game::{DataFrame, League, MatchStats, MyError};
+use crate::ratings::season_ratings;
+use crate::standings::league_table;
+
+// a module that saves every loaded league into a SQLite database, along with the tables derived from the games,
+// so other tools can query the same data with SQL. the games can be loaded back out of it in place of the csv.
+
+const SCHEMA: &str = "
+CREATE TABLE leagues (name TEXT PRIMARY KEY, code TEXT NOT NULL);
+CREATE TABLE teams (id INTEGER PRIMARY KEY, name TEXT NOT NULL UNIQUE);
+CREATE TABLE seasons (league TEXT NOT NULL REFERENCES leagues(name), season INTEGER NOT NULL, games INTEGER NOT NULL, champion INTEGER REFERENCES teams(id), PRIMARY KEY (league, season));
+CREATE TABLE games (
+    id INTEGER PRIMARY KEY,
+    league TEXT NOT NULL REFERENCES leagues(name),
+    season INTEGER NOT NULL,
+    week INTEGER NOT NULL,
+    date TEXT NOT NULL,
+    home INTEGER NOT NULL REFERENCES teams(id),
+    away INTEGER NOT NULL REFERENCES teams(id),
+    home_goals INTEGER NOT NULL,
+    away_goals INTEGER NOT NULL,
+    result TEXT NOT NULL,
+    ht_home_goals INTEGER,
+    ht_away_goals INTEGER,
+    home_shots INTEGER, away_shots INTEGER,
+    home_shots_on_target INTEGER, away_shots_on_target INTEGER,
+    home_corners INTEGER, away_corners INTEGER,
+    home_yellows INTEGER, away_yellows INTEGER,
+    home_reds INTEGER, away_reds INTEGER,
+    referee TEXT,
+    attendance INTEGER
+);
+CREATE TABLE standings (league TEXT NOT NULL, season INTEGER NOT NULL, position INTEGER NOT NULL, team INTEGER NOT NULL REFERENCES teams(id), played INTEGER NOT NULL, won INTEGER NOT NULL, drawn INTEGER NOT NULL, lost INTEGER NOT NULL, goals_for INTEGER NOT NULL, goals_against INTEGER NOT NULL, points INTEGER NOT NULL, PRIMARY KEY (league, season, position));
+CREATE TABLE ratings (league TEXT NOT NULL, season INTEGER NOT NULL, team INTEGER NOT NULL REFERENCES teams(id), rating REAL NOT NULL, PRIMARY KEY (league, season, team));
+CREATE INDEX games_by_season ON games (league, season);
+";
+
+const TABLES: [&str; 6] = ["leagues", "teams", "seasons", "games", "standings", "ratings"]; // every table in SCHEMA, each after the ones it references
+
+fn team_id(conn: &Connection, name: &str) -> rusqlite::Result<i64> {
+    // the id for a team, adding it to the teams table the first time it's seen.
+    conn.execute("INSERT OR IGNORE INTO teams (name) VALUES (?1)", [name])?;
+    conn.query_row("SELECT id FROM teams WHERE name = ?1", [name], |row| row.get(0))
+}
+
+pub fn write_database(df: &DataFrame, path: &str) -> Result<(), Box<dyn Error>> {
+    // writes every league in the DataFrame to a database at path, replacing the tables of one written here before.
+    // anything else already at the path is left alone: a file that isn't a database fails to open as one, and a database
+    // with tables of its own is refused rather than having tables with the same names as ours dropped from it.
+    let mut conn = Connection::open(path)?;
+    let tx = conn.transaction()?; // one transaction for the whole load, which is far faster than committing every row
+    let existing: Vec<String> = {
+        let mut query = tx.prepare("SELECT name FROM sqlite_master WHERE type = 'table'")?;
+        let names = query.query_map([], |row| row.get(0))?;
+        names.collect::<Result<Vec<String>, _>>()?
+    };
+    if let Some(other) = existing.iter().find(|t| !TABLES.contains(&t.as_str())) {
+        return Err(Box::new(MyError(format!("{} already holds a database with a {} table, so it wasn't overwritten", path, other))));
+    }
+    for table in TABLES.iter().rev() { // the referencing tables go first, so no foreign key is left dangling
+        tx.execute_batch(&format!("DROP TABLE IF EXISTS {}", table))?;
+    }
+    tx.execute_batch(SCHEMA)?;
+    for league in df.leagues() {
+        let games = df.league_games(&league.name);
+        tx.execute("INSERT INTO leagues (name, code) VALUES (?1, ?2)", params![league.name, league.code])?;
+        {
+            let mut insert = tx.prepare("INSERT INTO games (league, season, week, date, home, away, home_goals, away_goals, result, ht_home_goals, ht_away_goals, home_shots, away_shots, home_shots_on_target, away_shots_on_target, home_corners, away_corners, home_yellows, away_yellows, home_reds, away_reds, referee, attendance) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23)")?;
+            for game in games {
+                let stats = game.stats.clone().unwrap_or_default();
+                insert.execute(params![
+                    league.name, game.season, game.week, game.date.to_string(), team_id(&tx, &game.home)?, team_id(&tx, &game.away)?,
+                    game.home_goals, game.away_goals, game.result, game.ht_home_goals, game.ht_away_goals,
+                    stats.home_shots, stats.away_shots, stats.home_shots_on_target, stats.away_shots_on_target,
+                    stats.home_corners, stats.away_corners, stats.home_yellows, stats.away_yellows,
+                    stats.home_reds, stats.away_reds, stats.referee, stats.attendance,
+                ])?;
+            }
+        }
+        for season in crate::game::seasons_in(games) {
+            let table = league_table(games, season);
+            let count = games.iter().filter(|g| g.season == season).count();
+            tx.execute("INSERT INTO seasons (league, season, games, champion) VALUES (?1, ?2, ?3, ?4)", params![league.name, season, count, team_id(&tx, &table[0].team)?])?;
+            for (i, row) in table.iter().enumerate() {
+                tx.execute("INSERT INTO standings VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)", params![league.name, season, i + 1, team_id(&tx, &row.team)?, row.played, row.won, row.drawn, row.lost, row.goals_for, row.goals_against, row.points])?;
+            }
+        }
+        for rating in season_ratings(games) {
+            tx.execute("INSERT INTO ratings VALUES (?1, ?2, ?3, ?4)", params![league.name, rating.season, team_id(&tx, &rating.team)?, rating.rating])?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+pub fn leagues(path: &str) -> Result<Vec<League>, Box<dyn Error>> {
+    // the leagues stored in a database.
+    let conn = Connection::open(path)?;
+    let mut query = conn.prepare("SELECT name, code FROM leagues ORDER BY rowid")?;
+    let rows = query.query_map([], |row| Ok(League { name: row.get(0)?, code: row.get(1)? }))?;
+    Ok(rows.collect::<Result<Vec<League>, _>>()?)
+}
+
+pub fn read_games(path: &str, league: &League) -> Result<Vec<Game>, Box<dyn Error>> {
+    // reads one league's games back out of the database in the order they were written, as the same Games the csv gives.
+    let conn = Connection::open(path)?;
+    let mut query = conn.prepare("SELECT g.season, g.week, g.date, h.name, a.name, g.home_goals, g.away_goals, g.result, g.ht_home_goals, g.ht_away_goals,
+        g.home_shots, g.away_shots, g.home_shots_on_target, g.away_shots_on_target, g.home_corners, g.away_corners,
+        g.home_yellows, g.away_yellows, g.home_reds, g.away_reds, g.referee, g.attendance
+        FROM games g JOIN teams h ON h.id = g.home JOIN teams a ON a.id = g.away WHERE g.league = ?1 ORDER BY g.id")?;
+    let mut rows = query.query([&league.name])?;
+    let mut games = Vec::new();
+    while let Some(row) = rows.next()? {
+        let date: String = row.get(2)?;
+        let stats = MatchStats {
+            home_shots: row.get(10)?,
+            away_shots: row.get(11)?,
+            home_shots_on_target: row.get(12)?,
+            away_shots_on_target: row.get(13)?,
+            home_corners: row.get(14)?,
+            away_corners: row.get(15)?,
+            home_yellows: row.get(16)?,
+            away_yellows: row.get(17)?,
+            home_reds: row.get(18)?,
+            away_reds: row.get(19)?,
+            referee: row.get(20)?,
+            attendance: row.get(21)?,
+        };
+        games.push(Game {
+            season: row.get(0)?,
+            week: row.get(1)?,
+            date: NaiveDate::parse_from_str(&date, "%Y-%m-%d").map_err(|e| MyError(format!("Bad date '{}' in the database: {}", date, e)))?,
+            home: row.get(3)?,
+            away: row.get(4)?,
+            home_goals: row.get(5)?,
+            away_goals: row.get(6)?,
+            result: row.get(7)?,
+            competition: league.name.clone(),
+            ht_home_goals: row.get(8)?,
+            ht_away_goals: row.get(9)?,
+            stats: if stats == MatchStats::default() { None } else { Some(stats) },
+        });
+    }
+    Ok(games)
+}
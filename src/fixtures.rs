@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use serde::Serialize;
+use crate::Game;
+
+// a module for generating round-robin fixture lists and measuring how a schedule is laid out,
+// so a real season's fixtures can be compared against a balanced one or a hypothetical season can be built for any set of teams.
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Fixture {
+    pub week: usize,
+    pub home: String,
+    pub away: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduleStats {
+    // how a fixture list is laid out. a break is a team playing at home (or away) in two matchweeks running.
+    pub label: String,
+    pub teams: usize,
+    pub weeks: usize,
+    pub fixtures: usize,
+    pub breaks: usize,
+    pub most_breaks: usize, // the most breaks any one team has
+    pub longest_run: usize, // the most matchweeks in a row any team spends at home or away
+    pub mean_meeting_gap: f64, // the average number of matchweeks between a pair's two meetings
+    pub shortest_meeting_gap: usize,
+}
+
+fn single_round_robin(count: usize) -> Vec<Vec<(usize, usize)>> {
+    // one round of every team playing every other once, as (home, away) indices, for an even number of teams.
+    // this is the circle method: the last team stays put while the rest rotate around it, and venues are set so that
+    // each team alternates home and away as much as possible (the Berger tables used by most leagues).
+    let rotating = count - 1;
+    let mut rounds = Vec::new();
+    for round in 0..rotating {
+        let mut pairs = Vec::new();
+        pairs.push(if round.is_multiple_of(2) { (round, count - 1) } else { (count - 1, round) });
+        for k in 1..count / 2 {
+            let a = (round + k) % rotating;
+            let b = (round + rotating - k) % rotating;
+            pairs.push(if k % 2 == 1 { (a, b) } else { (b, a) });
+        }
+        rounds.push(pairs);
+    }
+    rounds
+}
+
+pub fn fixture_list(teams: &[String], weeks: usize) -> Vec<Fixture> {
+    // a fixture list of the given number of matchweeks. the usual length is two rounds (2 × (teams - 1) weeks for an even number),
+    // where the second round repeats the first with the venues swapped; more weeks start further rounds, fewer cut the last one short.
+    // with an odd number of teams one team sits out each week.
+    let mut names: Vec<Option<&String>> = teams.iter().map(Some).collect();
+    if names.len() % 2 == 1 {
+        names.push(None); // the bye
+    }
+    if names.len() < 2 {
+        return Vec::new();
+    }
+    let single = single_round_robin(names.len());
+    let mut fixtures = Vec::new();
+    for week in 0..weeks {
+        let leg = week / single.len();
+        for (home, away) in &single[week % single.len()] {
+            let (home, away) = if leg.is_multiple_of(2) { (home, away) } else { (away, home) };
+            if let (Some(home), Some(away)) = (names[*home], names[*away]) {
+                fixtures.push(Fixture { week: week + 1, home: home.clone(), away: away.clone() });
+            }
+        }
+    }
+    fixtures
+}
+
+pub fn double_round_robin(teams: &[String]) -> Vec<Fixture> {
+    // the standard league season: everyone plays everyone home and away.
+    let even = teams.len() + teams.len() % 2;
+    fixture_list(teams, 2 * (even - 1))
+}
+
+pub fn season_fixtures(games: &[Game], season: usize) -> Vec<Fixture> {
+    // a real season's fixtures by the matchweek they were scheduled in. postponed games keep their original week in the data.
+    let mut fixtures: Vec<Fixture> = games
+        .iter()
+        .filter(|g| g.season == season)
+        .map(|g| Fixture { week: g.week, home: g.home.clone(), away: g.away.clone() })
+        .collect();
+    fixtures.sort_by(|a, b| (a.week, &a.home).cmp(&(b.week, &b.home)));
+    fixtures
+}
+
+pub fn schedule_stats(label: &str, fixtures: &[Fixture]) -> ScheduleStats {
+    // measures the breaks, venue runs and gaps between meetings in a fixture list.
+    let mut venues: HashMap<&String, Vec<(usize, bool)>> = HashMap::new(); // each team's (week, at home) in order
+    let mut meetings: HashMap<(&String, &String), Vec<usize>> = HashMap::new();
+    for fixture in fixtures {
+        venues.entry(&fixture.home).or_default().push((fixture.week, true));
+        venues.entry(&fixture.away).or_default().push((fixture.week, false));
+        let pair = if fixture.home < fixture.away { (&fixture.home, &fixture.away) } else { (&fixture.away, &fixture.home) };
+        meetings.entry(pair).or_default().push(fixture.week);
+    }
+    let mut breaks = 0;
+    let mut most_breaks = 0;
+    let mut longest_run = 0;
+    for schedule in venues.values_mut() {
+        schedule.sort();
+        let mut team_breaks = 0;
+        let mut run = 1;
+        for pair in schedule.windows(2) {
+            if pair[0].1 == pair[1].1 {
+                team_breaks += 1;
+                run += 1;
+            } else {
+                run = 1;
+            }
+            longest_run = longest_run.max(run);
+        }
+        breaks += team_breaks;
+        most_breaks = most_breaks.max(team_breaks);
+    }
+    let gaps: Vec<usize> = meetings
+        .values_mut()
+        .filter(|weeks| weeks.len() > 1)
+        .flat_map(|weeks| {
+            weeks.sort();
+            weeks.windows(2).map(|w| w[1] - w[0]).collect::<Vec<usize>>()
+        })
+        .collect();
+    ScheduleStats {
+        label: label.to_string(),
+        teams: venues.len(),
+        weeks: fixtures.iter().map(|f| f.week).max().unwrap_or(0),
+        fixtures: fixtures.len(),
+        breaks,
+        most_breaks,
+        longest_run,
+        mean_meeting_gap: if gaps.is_empty() { 0.0 } else { gaps.iter().sum::<usize>() as f64 / gaps.len() as f64 },
+        shortest_meeting_gap: gaps.iter().copied().min().unwrap_or(0),
+    }
+}
@@ -1,5 +1,11 @@
 use std::error::Error;
 use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::collections::HashMap;
+use bincode::Options;
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
 
 // a module to read the csv and split it into the necessary Structs to analyze each game individually.
 
@@ -14,93 +20,443 @@ impl fmt::Display for MyError {
 }
 impl Error for MyError {}
 
+const CACHE_MAGIC: &[u8; 8] = b"PLCACHE4"; // change the last character whenever Snapshot changes shape, so old caches are ignored
+
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    // the form a DataFrame takes in the cache: each team name is stored once and games point to it by index,
+    // dates are stored as day numbers, and the result is left out because it follows from the score.
+    headers: Vec<String>,
+    league: League,
+    teams: Vec<String>,
+    games: Vec<CachedGame>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedGame {
+    season: usize,
+    week: usize,
+    day: i32,
+    home: usize,
+    away: usize,
+    home_goals: usize,
+    away_goals: usize,
+    half_time: Option<(usize, usize)>,
+    stats: Option<MatchStats>,
+}
+
+pub const KNOWN_LEAGUES: [(&str, &str); 10] = [
+    // (code, name) for the leagues we have data for, using the division codes from football-data.co.uk.
+    ("E0", "Premier League"),
+    ("E1", "Championship"),
+    ("E2", "League One"),
+    ("E3", "League Two"),
+    ("SC0", "Scottish Premiership"),
+    ("SP1", "La Liga"),
+    ("D1", "Bundesliga"),
+    ("I1", "Serie A"),
+    ("F1", "Ligue 1"),
+    ("N1", "Eredivisie"),
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct League {
+    // a competition that games can belong to. every Game carries its league's name so several leagues can share one DataFrame.
+    pub name: String,
+    pub code: String,
+}
+
+impl League {
+    pub fn new(name: &str, code: &str) -> Self {
+        League { name: name.to_string(), code: code.to_string() }
+    }
+
+    pub fn premier_league() -> Self {
+        League::new("Premier League", "E0")
+    }
+
+    pub fn from_name(name: &str) -> Self {
+        // looks the league up in KNOWN_LEAGUES for its code, falling back to the name itself for leagues we don't know.
+        match KNOWN_LEAGUES.iter().find(|(_, n)| *n == name) {
+            Some((code, _)) => League::new(name, code),
+            None => League::new(name, name),
+        }
+    }
+}
+
 pub struct DataFrame {
     // a struct that holds information about the whole csv, representing all match results.
     // used to keep information about the headers and rows consistent and store all the games in one record.
+    // games from several leagues can be loaded into the same DataFrame; they're kept grouped by league so each league is one slice.
     headers: Vec<String>,
+    leagues: Vec<League>,
     games: Vec<Game>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Game {
     // an individual record of each game with the information accompanying it from the csv.
+    // the renames map the csv's column headers onto the fields when reading; everything else still sees the field names.
+    #[serde(rename(deserialize = "Season_End_Year"))]
     pub season: usize,
-    week: usize,
-    date: String,
+    #[serde(rename(deserialize = "Wk"))]
+    pub week: usize,
+    #[serde(rename(deserialize = "Date"))]
+    pub date: NaiveDate,
+    #[serde(rename(deserialize = "Home"))]
     pub home: String,
+    #[serde(rename(deserialize = "Away"))]
     pub away: String,
+    #[serde(rename(deserialize = "HomeGoals"))]
     pub home_goals: usize,
+    #[serde(rename(deserialize = "AwayGoals"))]
     pub away_goals: usize,
+    #[serde(rename(deserialize = "FTR"))]
     pub result: String,
+    #[serde(skip_deserializing)]
+    pub competition: String, // the name of the league the game was played in, filled in by whichever loader read it
+    #[serde(rename(deserialize = "HTHG"), default)]
+    pub ht_home_goals: Option<usize>, // the half-time score, for sources that have it
+    #[serde(rename(deserialize = "HTAG"), default)]
+    pub ht_away_goals: Option<usize>,
+    #[serde(skip_deserializing)]
+    pub stats: Option<MatchStats>, // filled in from the extra columns by MatchStats::from_record, when the source has any of them
+}
+
+pub const STATS_COLUMNS: [&str; 12] = ["HS", "AS", "HST", "AST", "HC", "AC", "HY", "AY", "HR", "AR", "Referee", "Attendance"]; // football-data.co.uk's names for them
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MatchStats {
+    // the extra numbers some sources have for a game. each one is optional on its own, since sources differ in which columns they carry.
+    pub home_shots: Option<usize>,
+    pub away_shots: Option<usize>,
+    pub home_shots_on_target: Option<usize>,
+    pub away_shots_on_target: Option<usize>,
+    pub home_corners: Option<usize>,
+    pub away_corners: Option<usize>,
+    pub home_yellows: Option<usize>,
+    pub away_yellows: Option<usize>,
+    pub home_reds: Option<usize>,
+    pub away_reds: Option<usize>,
+    pub referee: Option<String>,
+    pub attendance: Option<usize>,
+}
+
+impl MatchStats {
+    pub fn from_record(headers: &csv::StringRecord, record: &csv::StringRecord) -> Option<Self> {
+        // reads the STATS_COLUMNS out of a csv row by name, or None if the row has none of them filled in.
+        let field = |name: &str| headers.iter().position(|h| h == name).and_then(|i| record.get(i)).map(|f| f.trim()).filter(|f| !f.is_empty());
+        let number = |name: &str| field(name).and_then(|f| f.replace(',', "").parse::<usize>().ok()); // attendances are sometimes written as 34,000
+        let stats = MatchStats {
+            home_shots: number("HS"),
+            away_shots: number("AS"),
+            home_shots_on_target: number("HST"),
+            away_shots_on_target: number("AST"),
+            home_corners: number("HC"),
+            away_corners: number("AC"),
+            home_yellows: number("HY"),
+            away_yellows: number("AY"),
+            home_reds: number("HR"),
+            away_reds: number("AR"),
+            referee: field("Referee").map(|r| r.to_string()),
+            attendance: number("Attendance"),
+        };
+        if stats == MatchStats::default() { None } else { Some(stats) }
+    }
+
+    fn to_record(&self) -> Vec<String> {
+        // the values in STATS_COLUMNS order, with blanks for the missing ones.
+        let numbers = [self.home_shots, self.away_shots, self.home_shots_on_target, self.away_shots_on_target, self.home_corners, self.away_corners, self.home_yellows, self.away_yellows, self.home_reds, self.away_reds];
+        let mut record: Vec<String> = numbers.iter().map(|n| n.map(|n| n.to_string()).unwrap_or_default()).collect();
+        record.push(self.referee.clone().unwrap_or_default());
+        record.push(self.attendance.map(|n| n.to_string()).unwrap_or_default());
+        record
+    }
 }
 impl DataFrame {
     pub fn new() -> Self {
         // creates a new empty instance of the DataFrame struct.
         DataFrame {
             headers: Vec::new(),
+            leagues: Vec::new(),
             games: Vec::new(),
         }
     }
-    pub fn read_csv(&mut self, path: &str) -> Result<Vec<Game>, Box<dyn Error>> {
-        // reads from the csv. intiializes an individual vector for each game "field"
-        // uses a match statement to push info from each column to the correct vector, then collects those into individual games at the end
-        let mut rdr = csv::ReaderBuilder::new()
-            .delimiter(b',')
-            .has_headers(true)
-            .flexible(true)
-            .from_path(path)?;
-        let mut seasons = Vec::new();
-        let mut weeks = Vec::new();
-        let mut dates = Vec::new();
-        let mut homes = Vec::new();
-        let mut aways = Vec::new();
-        let mut home_scores = Vec::new();
-        let mut away_scores = Vec::new();
-        let mut ftrs = Vec::new();
-        for h in rdr.headers()? {
-            self.headers.push((*h).to_string());
-        }
-        for result in rdr.records() {
-            let r = result.unwrap();
-            for (i, elem) in r.iter().enumerate() {
-                match i { // each column will be used, so just match the column based on order and push the info from that row to the correct vector
-                    0 => seasons.push(elem.parse::<usize>().unwrap()),
-                    1 => weeks.push(elem.parse::<usize>().unwrap()),
-                    2 => dates.push(elem.to_string()),
-                    3 => homes.push(elem.to_string()),
-                    4 => home_scores.push(elem.parse::<usize>().unwrap()),
-                    5 => away_scores.push(elem.parse::<usize>().unwrap()),
-                    6 => aways.push(elem.to_string()),
-                    7 => ftrs.push(elem.to_string()),
-                    _ => return Err(Box::new(MyError("Unknown type".to_string()))),
-                }
+
+    pub fn read_csv(&mut self, path: &str, league: League) -> Result<&[Game], Box<dyn Error>> {
+        // reads from the csv in a single pass, deserializing each row straight into a Game tagged with the league.
+        // returns a view of every game in that league read so far, rather than a copy of them.
+        // files in football-data.co.uk's layout are recognised by their headers and read through the importer instead.
+        let stream = stream_csv(path, &league)?;
+        if crate::import::is_football_data(&stream.headers) {
+            let (headers, games) = crate::import::read_football_data(path, &league)?;
+            self.add_headers(&headers);
+            self.add_games(&league, games);
+            return Ok(self.league_games(&league.name));
+        }
+        self.add_headers(&stream.headers);
+        let mut games = Vec::new();
+        for game in stream {
+            games.push(game?);
+        }
+        self.add_games(&league, games);
+        Ok(self.league_games(&league.name))
+    }
+
+    pub fn read_cached(&mut self, path: &str, league: League) -> Result<&[Game], Box<dyn Error>> {
+        // loads the games from the binary cache next to the csv if it was built from the same file for the same league, skipping the csv parsing.
+        // if the cache is missing or the csv has changed since, reads the csv as normal and rewrites the cache.
+        let hash = source_hash(&std::fs::read(path)?, &league);
+        if let Some(snapshot) = load_cache(&cache_path(path), hash) {
+            self.add_headers(&snapshot.headers);
+            self.add_games(&league, snapshot.games()?);
+            return Ok(self.league_games(&league.name));
+        }
+        let mut fresh = DataFrame::new();
+        fresh.read_csv(path, league.clone())?;
+        if let Err(e) = write_cache(&cache_path(path), hash, &fresh.headers, &league, &fresh.games) {
+            println!("Could not write the cache for {}: {}", path, e); // not fatal, the next run will just read the csv again
+        }
+        self.add_headers(&fresh.headers);
+        self.add_games(&league, fresh.games);
+        Ok(self.league_games(&league.name))
+    }
+
+    pub fn read_sqlite(&mut self, path: &str, league: League) -> Result<&[Game], Box<dyn Error>> {
+        // loads one league's games from a database written by database::write_database, in place of its csv.
+        let games = crate::database::read_games(path, &league)?;
+        self.add_games(&league, games);
+        Ok(self.league_games(&league.name))
+    }
+
+    pub fn read_parquet(&mut self, path: &str) -> Result<&[Game], Box<dyn Error>> {
+        // loads every league in a Parquet file written by columnar::write_parquet, returning all the games.
+        let games = crate::columnar::read_parquet(path)?;
+        self.add_tagged_games(games);
+        Ok(&self.games)
+    }
+
+    pub fn read_ipc(&mut self, path: &str) -> Result<&[Game], Box<dyn Error>> {
+        // the same for an Arrow IPC file written by columnar::write_ipc.
+        let games = crate::columnar::read_ipc(path)?;
+        self.add_tagged_games(games);
+        Ok(&self.games)
+    }
+
+    fn add_tagged_games(&mut self, games: Vec<Game>) {
+        // adds games that already carry their league's name, registering each league the first time it appears.
+        for game in &games {
+            if !self.leagues.iter().any(|l| l.name == game.competition) {
+                self.leagues.push(League::from_name(&game.competition));
+            }
+        }
+        self.games.extend(games);
+        self.games.sort_by(|a, b| a.competition.cmp(&b.competition));
+    }
+
+    pub fn rebuild_cache(path: &str, league: League) -> Result<usize, Box<dyn Error>> {
+        // re-reads the csv from scratch and overwrites the cache, returning the number of games stored.
+        let hash = source_hash(&std::fs::read(path)?, &league);
+        let mut fresh = DataFrame::new();
+        fresh.read_csv(path, league.clone())?;
+        write_cache(&cache_path(path), hash, &fresh.headers, &league, &fresh.games)?;
+        Ok(fresh.games.len())
+    }
+
+    fn add_headers(&mut self, headers: &[String]) {
+        // keeps every column name seen across all the files read, once each.
+        for h in headers {
+            if !self.headers.contains(h) {
+                self.headers.push(h.clone());
             }
         }
-        for (i, elem) in homes.iter().enumerate() {
-            // every vector is the same length, so just loop through the length and create a new Game instance for each one
-            // then push that Game to the games field of the dataframe to keep a record of all games
-            let game = Game::new(seasons[i], weeks[i], dates[i].clone(), elem.to_string(), aways[i].clone(), home_scores[i], away_scores[i], ftrs[i].clone());
-            self.games.push(game)
+    }
+
+    fn add_games(&mut self, league: &League, games: Vec<Game>) {
+        // adds games to the store and regroups them by league. the sort is stable, so each league's games stay in the order they were read.
+        if !self.leagues.contains(league) {
+            self.leagues.push(league.clone());
+        }
+        self.games.extend(games);
+        self.games.sort_by(|a, b| a.competition.cmp(&b.competition));
+    }
+
+    pub fn games(&self) -> &[Game] {
+        // every game in every league, for metrics run across leagues.
+        &self.games
+    }
+
+    pub fn leagues(&self) -> &[League] {
+        &self.leagues
+    }
+
+    pub fn league_games(&self, name: &str) -> &[Game] {
+        // the games from one league, as a slice that every metric function can take. empty if the league isn't loaded.
+        let start = self.games.partition_point(|g| g.competition.as_str() < name);
+        let end = self.games.partition_point(|g| g.competition.as_str() <= name);
+        &self.games[start..end]
+    }
+}
+
+impl Snapshot {
+    fn games(&self) -> Result<Vec<Game>, Box<dyn Error>> {
+        // turns the cached games back into Games, looking up team names and rebuilding dates and results.
+        let mut games = Vec::with_capacity(self.games.len());
+        for cached in &self.games {
+            let result = if cached.home_goals > cached.away_goals { "H" } else if cached.home_goals < cached.away_goals { "A" } else { "D" };
+            let corrupt = || MyError("The cache is corrupt; run rebuild-cache".to_string());
+            games.push(Game {
+                season: cached.season,
+                week: cached.week,
+                date: NaiveDate::from_num_days_from_ce_opt(cached.day).ok_or_else(corrupt)?,
+                home: self.teams.get(cached.home).ok_or_else(corrupt)?.clone(),
+                away: self.teams.get(cached.away).ok_or_else(corrupt)?.clone(),
+                home_goals: cached.home_goals,
+                away_goals: cached.away_goals,
+                result: result.to_string(),
+                competition: self.league.name.clone(),
+                ht_home_goals: cached.half_time.map(|(home, _)| home),
+                ht_away_goals: cached.half_time.map(|(_, away)| away),
+                stats: cached.stats.clone(),
+            });
         }
-        Ok(self.games.clone())
+        Ok(games)
     }
 }
 
-impl Game {
-    fn new(season: usize, week: usize, date: String, home: String, away: String, home_goals: usize, away_goals: usize, result: String) -> Self {
-        // a constructor method to create a new Game instance.
-        Game {
-            season,
-            week,
-            date,
-            home,
-            away,
-            home_goals,
-            away_goals,
-            result,
+fn load_cache(cache: &str, hash: u64) -> Option<Snapshot> {
+    // the cache starts with the magic bytes and the hash of the csv it was built from; anything else counts as stale.
+    let mut file = BufReader::new(File::open(cache).ok()?);
+    let mut header = [0u8; 16];
+    file.read_exact(&mut header).ok()?;
+    if header[..8] != CACHE_MAGIC[..] || header[8..] != hash.to_le_bytes() {
+        return None;
+    }
+    bincode::DefaultOptions::new().deserialize_from(file).ok()
+}
+
+fn write_cache(cache: &str, hash: u64, headers: &[String], league: &League, games: &[Game]) -> Result<(), Box<dyn Error>> {
+    // writes the header followed by the snapshot of one league's games, with bincode's variable-length integers to keep the file small.
+    let mut teams: Vec<String> = Vec::new();
+    let mut index = HashMap::new();
+    let mut cached_games = Vec::with_capacity(games.len());
+    for game in games {
+        let mut team_index = |team: &String| *index.entry(team.clone()).or_insert_with(|| {
+            teams.push(team.clone());
+            teams.len() - 1
+        });
+        cached_games.push(CachedGame {
+            season: game.season,
+            week: game.week,
+            day: game.date.num_days_from_ce(),
+            home: team_index(&game.home),
+            away: team_index(&game.away),
+            home_goals: game.home_goals,
+            away_goals: game.away_goals,
+            half_time: game.ht_home_goals.zip(game.ht_away_goals),
+            stats: game.stats.clone(),
+        });
+    }
+    let snapshot = Snapshot { headers: headers.to_vec(), league: league.clone(), teams, games: cached_games };
+    let mut file = BufWriter::new(File::create(cache)?);
+    file.write_all(CACHE_MAGIC)?;
+    file.write_all(&hash.to_le_bytes())?;
+    bincode::DefaultOptions::new().serialize_into(&mut file, &snapshot)?;
+    file.flush()?;
+    Ok(())
+}
+
+pub struct GameStream {
+    // reads the csv one game at a time, for callers that only need to pass over the games once without holding them all in memory.
+    pub headers: Vec<String>,
+    competition: String,
+    header_record: csv::StringRecord,
+    records: csv::StringRecordsIntoIter<File>,
+}
+
+impl Iterator for GameStream {
+    type Item = Result<Game, csv::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // tags each game with the league the stream was opened for, and picks up any extra stats columns.
+        let record = match self.records.next()? {
+            Ok(record) => record,
+            Err(e) => return Some(Err(e)),
+        };
+        let mut game = record.deserialize::<Game>(Some(&self.header_record));
+        if let Ok(game) = &mut game {
+            game.competition = self.competition.clone();
+            game.stats = MatchStats::from_record(&self.header_record, &record);
+        }
+        Some(game)
+    }
+}
+
+pub fn stream_csv(path: &str, league: &League) -> Result<GameStream, Box<dyn Error>> {
+    // opens the csv and reads its headers; the games are only read as the stream is iterated.
+    let mut rdr = csv::ReaderBuilder::new()
+        .delimiter(b',')
+        .has_headers(true)
+        .flexible(true)
+        .from_path(path)?;
+    let header_record = rdr.headers()?.clone();
+    let headers = header_record.iter().map(|h| h.to_string()).collect();
+    Ok(GameStream { headers, competition: league.name.clone(), header_record, records: rdr.into_records() })
+}
+
+pub fn write_csv(path: &str, games: &[Game]) -> Result<(), Box<dyn Error>> {
+    // writes games out in the same layout as pl_matches.csv, so imported seasons can be added to the dataset.
+    // the half-time score and the extra stats are added as extra columns when any of the games has them.
+    let mut wtr = csv::WriterBuilder::new().terminator(csv::Terminator::CRLF).from_path(path)?; // the same line endings as the original file
+    let half_time = games.iter().any(|g| g.ht_home_goals.is_some());
+    let mut headers = vec!["Season_End_Year", "Wk", "Date", "Home", "HomeGoals", "AwayGoals", "Away", "FTR"];
+    let stats = games.iter().any(|g| g.stats.is_some());
+    if half_time {
+        headers.extend(["HTHG", "HTAG"]);
+    }
+    if stats {
+        headers.extend(STATS_COLUMNS);
+    }
+    wtr.write_record(&headers)?;
+    let optional = |goals: Option<usize>| goals.map(|g| g.to_string()).unwrap_or_default();
+    for game in games {
+        let mut record = vec![game.season.to_string(), game.week.to_string(), game.date.to_string(), game.home.clone(), game.home_goals.to_string(), game.away_goals.to_string(), game.away.clone(), game.result.clone()];
+        if half_time {
+            record.extend([optional(game.ht_home_goals), optional(game.ht_away_goals)]);
+        }
+        if stats {
+            record.extend(game.stats.clone().unwrap_or_default().to_record());
         }
+        wtr.write_record(&record)?;
     }
+    wtr.flush()?;
+    Ok(())
+}
 
+pub fn cache_path(path: &str) -> String {
+    // the cache for "pl_matches.csv" lives beside it as "pl_matches.csv.cache".
+    format!("{}.cache", path)
+}
+
+fn source_hash(bytes: &[u8], league: &League) -> u64 {
+    // a 64-bit FNV-1a hash of the csv and the league it's read as. unlike the standard library's hasher it is guaranteed not to change between Rust versions.
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes.iter().chain(league.name.as_bytes()) {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+pub fn seasons_in(games: &[Game]) -> Vec<usize> {
+    // every season that has at least one game, in order.
+    let mut seasons: Vec<usize> = games.iter().map(|g| g.season).collect();
+    seasons.sort();
+    seasons.dedup();
+    seasons
+}
+
+impl Game {
     pub fn print(&self) {
         // to print the info from each game more elegantly, with more information based on the result.
         if self.result == "H".to_string() {
@@ -0,0 +1,132 @@
+use std::collections::HashSet;
+use serde::Serialize;
+use crate::Game;
+
+// a module for the games that have a half-time score: how often leads are held or thrown away, and how the goals split between the halves.
+// games without a half-time score are left out of everything here, so a source without them just gives empty results.
+
+pub const STATES: [&str; 3] = ["winning", "level", "losing"]; // the rows and columns of a transition matrix, from the side's point of view
+
+fn state(scored: usize, conceded: usize) -> usize {
+    // the index into STATES for a score.
+    if scored > conceded { 0 } else if scored == conceded { 1 } else { 2 }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Transitions {
+    // how games stood at half-time (rows) against how they finished (columns), from one side's point of view.
+    // for a team that side is the team; for the whole league it's the home side.
+    pub label: String,
+    pub counts: [[usize; 3]; 3],
+}
+
+impl Transitions {
+    pub fn games(&self) -> usize {
+        self.counts.iter().flatten().sum()
+    }
+
+    pub fn comeback_wins(&self) -> usize {
+        // games won from losing at half-time.
+        self.counts[2][0]
+    }
+
+    pub fn comeback_draws(&self) -> usize {
+        // games drawn from losing at half-time.
+        self.counts[2][1]
+    }
+
+    pub fn points_won_from_losing(&self) -> usize {
+        // points taken from games the side was losing at half-time.
+        3 * self.comeback_wins() + self.comeback_draws()
+    }
+
+    pub fn points_dropped_from_winning(&self) -> usize {
+        // points given up in games the side was winning at half-time: two for a draw, three for a defeat.
+        2 * self.counts[0][1] + 3 * self.counts[0][2]
+    }
+
+    pub fn hold_rate(&self, half_time: usize) -> Option<f64> {
+        // the percentage of games in a half-time state that finished in the same state.
+        let total: usize = self.counts[half_time].iter().sum();
+        if total == 0 {
+            return None;
+        }
+        Some(self.counts[half_time][half_time] as f64 / total as f64 * 100.0)
+    }
+
+    pub fn print(&self) {
+        // prints the matrix with each row as percentages of the games in that half-time state.
+        println!("{} ({} games), half-time down the side, full-time across:", self.label, self.games());
+        println!("{:>10} {:>9} {:>9} {:>9}", "", STATES[0], STATES[1], STATES[2]);
+        for (i, row) in self.counts.iter().enumerate() {
+            let total: usize = row.iter().sum();
+            let pct = |n: usize| if total == 0 { 0.0 } else { n as f64 / total as f64 * 100.0 };
+            println!("{:>10} {:>8.2}% {:>8.2}% {:>8.2}%", STATES[i], pct(row[0]), pct(row[1]), pct(row[2]));
+        }
+    }
+}
+
+pub fn transitions(games: &[Game], team: Option<&String>, seasons: &[usize]) -> Transitions {
+    // the half-time to full-time matrix for a team, or for the home sides of the whole league with None.
+    let mut matrix = Transitions { label: team.cloned().unwrap_or_else(|| "Home sides".to_string()), ..Default::default() };
+    for game in games.iter().filter(|g| seasons.contains(&g.season)) {
+        let (Some(ht_home), Some(ht_away)) = (game.ht_home_goals, game.ht_away_goals) else {
+            continue;
+        };
+        let home_side = match team {
+            None => true,
+            Some(t) if *t == game.home => true,
+            Some(t) if *t == game.away => false,
+            Some(_) => continue,
+        };
+        let (half, full) = if home_side {
+            (state(ht_home, ht_away), state(game.home_goals, game.away_goals))
+        } else {
+            (state(ht_away, ht_home), state(game.away_goals, game.home_goals))
+        };
+        matrix.counts[half][full] += 1;
+    }
+    matrix
+}
+
+pub fn second_half_share(games: &[Game], team: Option<&String>, seasons: &[usize]) -> Option<f64> {
+    // the percentage of goals scored after half-time: by the team when one is given, otherwise by everyone.
+    let mut first = 0;
+    let mut second = 0;
+    for game in games.iter().filter(|g| seasons.contains(&g.season)) {
+        let (Some(ht_home), Some(ht_away)) = (game.ht_home_goals, game.ht_away_goals) else {
+            continue;
+        };
+        let (ht_goals, ft_goals) = match team {
+            None => (ht_home + ht_away, game.home_goals + game.away_goals),
+            Some(t) if *t == game.home => (ht_home, game.home_goals),
+            Some(t) if *t == game.away => (ht_away, game.away_goals),
+            Some(_) => continue,
+        };
+        first += ht_goals;
+        second += ft_goals.saturating_sub(ht_goals); // guards against a bad row where the half-time score is higher than the final one
+    }
+    if first + second == 0 {
+        return None;
+    }
+    Some(second as f64 / (first + second) as f64 * 100.0)
+}
+
+pub fn comeback_table(games: &[Game], teams: &HashSet<String>, seasons: &[usize]) -> Vec<Transitions> {
+    // every team's matrix over the seasons, ordered by the points they won from losing positions, then by the fewest points dropped from winning ones.
+    let mut table: Vec<Transitions> = teams
+        .iter()
+        .map(|team| transitions(games, Some(team), seasons))
+        .filter(|m| m.games() > 0)
+        .collect();
+    table.sort_by(|a, b| {
+        b.points_won_from_losing().cmp(&a.points_won_from_losing())
+            .then(a.points_dropped_from_winning().cmp(&b.points_dropped_from_winning()))
+            .then(a.label.cmp(&b.label))
+    });
+    table
+}
+
+pub fn has_half_times(games: &[Game]) -> bool {
+    games.iter().any(|g| g.ht_home_goals.is_some() && g.ht_away_goals.is_some())
+}
@@ -0,0 +1,179 @@
+use std::collections::HashMap;
+use std::error::Error;
+use chrono::{Datelike, NaiveDate};
+use serde::Deserialize;
+use crate::Game;
+use crate::game::{League, MatchStats, MyError, KNOWN_LEAGUES};
+
+// a module that reads the csv layout used by football-data.co.uk (Div, Date, HomeTeam, AwayTeam, FTHG, FTAG, FTR, ...) into Games,
+// so newer seasons and other divisions can be loaded without converting the files by hand.
+// those files have no season or matchweek columns, so both are worked out from the dates.
+
+pub const TEAM_ALIASES: [(&str, &str); 22] = [
+    // (football-data name, our name) for the teams whose names differ between the two sources.
+    ("Birmingham", "Birmingham City"),
+    ("Bradford", "Bradford City"),
+    ("Cardiff", "Cardiff City"),
+    ("Charlton", "Charlton Ath"),
+    ("Coventry", "Coventry City"),
+    ("Derby", "Derby County"),
+    ("Hull", "Hull City"),
+    ("Ipswich", "Ipswich Town"),
+    ("Leeds", "Leeds United"),
+    ("Leicester", "Leicester City"),
+    ("Luton", "Luton Town"),
+    ("Man City", "Manchester City"),
+    ("Man United", "Manchester Utd"),
+    ("Newcastle", "Newcastle Utd"),
+    ("Norwich", "Norwich City"),
+    ("Nott'm Forest", "Nott'ham Forest"),
+    ("Oldham", "Oldham Athletic"),
+    ("Sheffield United", "Sheffield Utd"),
+    ("Stoke", "Stoke City"),
+    ("Swansea", "Swansea City"),
+    ("Swindon", "Swindon Town"),
+    ("Wigan", "Wigan Athletic"),
+];
+
+#[derive(Debug, Deserialize)]
+struct Row {
+    // one line of a football-data.co.uk file. the columns a Game holds are read here and the extra stats by MatchStats::from_record; the rest (the betting odds) are ignored.
+    // the aliases cover the shorter headers used in their files for leagues outside the main European divisions.
+    #[serde(rename = "Div", alias = "League")]
+    division: Option<String>,
+    #[serde(rename = "Season_End_Year")]
+    season: Option<usize>,
+    #[serde(rename = "Wk")]
+    week: Option<usize>,
+    #[serde(rename = "Date")]
+    date: String,
+    #[serde(rename = "HomeTeam", alias = "Home")]
+    home: String,
+    #[serde(rename = "AwayTeam", alias = "Away")]
+    away: String,
+    #[serde(rename = "FTHG", alias = "HG")]
+    home_goals: Option<usize>,
+    #[serde(rename = "FTAG", alias = "AG")]
+    away_goals: Option<usize>,
+    #[serde(rename = "HTHG")]
+    ht_home_goals: Option<usize>,
+    #[serde(rename = "HTAG")]
+    ht_away_goals: Option<usize>,
+}
+
+pub fn is_football_data(headers: &[String]) -> bool {
+    // whether a csv's headers are football-data.co.uk's rather than our own.
+    headers.iter().any(|h| h == "HomeTeam" || h == "HG")
+}
+
+fn text(field: &[u8]) -> String {
+    // the older football-data files are Latin-1 rather than UTF-8, so fall back to reading each byte as a character.
+    match std::str::from_utf8(field) {
+        Ok(s) => s.trim().to_string(),
+        Err(_) => field.iter().map(|b| *b as char).collect::<String>().trim().to_string(),
+    }
+}
+
+pub fn team_name(name: &str) -> String {
+    // our name for a team, so imported games line up with the ones already in the dataset.
+    match TEAM_ALIASES.iter().find(|(alias, _)| *alias == name) {
+        Some((_, ours)) => ours.to_string(),
+        None => name.to_string(),
+    }
+}
+
+pub fn parse_date(date: &str) -> Result<NaiveDate, Box<dyn Error>> {
+    // football-data writes dates as dd/mm/yy in older files and dd/mm/yyyy in newer ones.
+    let year = date.rsplit('/').next().unwrap_or("");
+    let format = if year.len() == 4 { "%d/%m/%Y" } else { "%d/%m/%y" };
+    NaiveDate::parse_from_str(date, format).map_err(|e| MyError(format!("Could not read the date '{}': {}", date, e)).into())
+}
+
+pub fn derive_seasons(games: &mut [Game], known: &[bool]) {
+    // fills in the season for every game where known is false, naming seasons by the year they end like the rest of the data.
+    // games from August on start a new season. July is ambiguous: the 2019-20 season ran into late July, and some divisions start in it,
+    // so a July game ends the old season if it's within three weeks of the game before it or more than three weeks before the game after it.
+    let mut order: Vec<usize> = (0..games.len()).collect();
+    order.sort_by_key(|i| games[*i].date);
+    let close = |a: NaiveDate, b: NaiveDate| (b - a).num_days() <= 21;
+    for (n, i) in order.iter().enumerate() {
+        let date = games[*i].date;
+        if known[*i] {
+            continue;
+        }
+        let previous = n.checked_sub(1).map(|p| games[order[p]].date);
+        let next = order.get(n + 1).map(|p| games[*p].date);
+        let ends_season = previous.is_some_and(|p| close(p, date)) || next.is_some_and(|x| !close(date, x));
+        games[*i].season = match date.month() {
+            8..=12 => date.year() as usize + 1,
+            7 if !ends_season => date.year() as usize + 1,
+            _ => date.year() as usize,
+        };
+    }
+}
+
+pub fn derive_weeks(games: &mut [Game], known: &[bool]) {
+    // fills in the matchweek for every game where known is false. a game's week is one more than the number of league games
+    // either team has already played that season, which matches the official round except around postponed games.
+    let mut order: Vec<usize> = (0..games.len()).collect();
+    order.sort_by_key(|i| (games[*i].season, games[*i].date));
+    let mut played: HashMap<(usize, String), usize> = HashMap::new();
+    for i in order {
+        let season = games[i].season;
+        let home = *played.get(&(season, games[i].home.clone())).unwrap_or(&0);
+        let away = *played.get(&(season, games[i].away.clone())).unwrap_or(&0);
+        if !known[i] {
+            games[i].week = home.max(away) + 1;
+        }
+        played.insert((season, games[i].home.clone()), home + 1);
+        played.insert((season, games[i].away.clone()), away + 1);
+    }
+}
+
+pub fn read_football_data(path: &str, league: &League) -> Result<(Vec<String>, Vec<Game>), Box<dyn Error>> {
+    // reads a football-data.co.uk csv into Games tagged with the league, returning the headers alongside them.
+    // rows for another division are skipped when the league is one we know the code of, as are blank rows and games with no score yet.
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(true)
+        .flexible(true)
+        .from_path(path)?;
+    let headers: Vec<String> = rdr.byte_headers()?.iter().map(|h| text(h).trim_start_matches('\u{feff}').to_string()).collect();
+    let header_record = csv::StringRecord::from(headers.clone());
+    let filter_division = KNOWN_LEAGUES.iter().any(|(code, _)| *code == league.code);
+    let mut games = Vec::new();
+    let mut season_known = Vec::new();
+    let mut week_known = Vec::new();
+    for record in rdr.byte_records() {
+        let record: csv::StringRecord = record?.iter().map(text).collect();
+        if record.iter().all(|f| f.is_empty()) {
+            continue;
+        }
+        let row: Row = record.deserialize(Some(&header_record))?;
+        if filter_division && row.division.as_ref().is_some_and(|d| *d != league.code) {
+            continue;
+        }
+        let (Some(home_goals), Some(away_goals)) = (row.home_goals, row.away_goals) else {
+            continue;
+        };
+        let result = if home_goals > away_goals { "H" } else if home_goals < away_goals { "A" } else { "D" };
+        season_known.push(row.season.is_some());
+        week_known.push(row.week.is_some());
+        games.push(Game {
+            season: row.season.unwrap_or(0),
+            week: row.week.unwrap_or(0),
+            date: parse_date(&row.date)?,
+            home: team_name(&row.home),
+            away: team_name(&row.away),
+            home_goals,
+            away_goals,
+            result: result.to_string(),
+            competition: league.name.clone(),
+            ht_home_goals: row.ht_home_goals,
+            ht_away_goals: row.ht_away_goals,
+            stats: MatchStats::from_record(&header_record, &record),
+        });
+    }
+    derive_seasons(&mut games, &season_known);
+    derive_weeks(&mut games, &week_known);
+    Ok((headers, games))
+}
@@ -0,0 +1,154 @@
+use serde::Serialize;
+use crate::Game;
+use crate::standings::{league_table, TableRow};
+use crate::stats::{poisson, MAX_GOALS};
+
+// a module for how many points each team "should" have had from its goals for and against, compared with what it got.
+// the gap is usually put down to luck in close games, and if it is luck it shouldn't carry over into the next season.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Model {
+    Pythagorean(f64), // with the exponent, e.g. from fit_exponent
+    Poisson,
+}
+
+impl Model {
+    pub fn describe(&self) -> String {
+        match self {
+            Model::Pythagorean(exponent) => format!("Pythagorean (exponent {:.2})", exponent),
+            Model::Poisson => "Poisson".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LuckRow {
+    pub season: usize,
+    pub team: String,
+    pub played: usize,
+    pub goals_for: usize,
+    pub goals_against: usize,
+    pub points: usize,
+    pub expected_points: f64,
+}
+
+impl LuckRow {
+    pub fn difference(&self) -> f64 {
+        // points above (or below) expectation.
+        self.points as f64 - self.expected_points
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RegressionToMean {
+    // whether beating the expected points one season carries over to the next, over every pair of consecutive seasons a team played.
+    pub pairs: usize,
+    pub correlation: f64,
+    pub threshold: f64,
+    pub overperformers: usize, // team-seasons at least `threshold` points above expectation
+    pub overperformers_next: f64, // their average difference the season after
+    pub underperformers: usize,
+    pub underperformers_next: f64,
+}
+
+fn poisson_points(scored: f64, conceded: f64) -> f64 {
+    // the expected points per game for a side whose goals for and against per game are independent Poisson draws.
+    let mut win = 0.0;
+    let mut draw = 0.0;
+    for x in 0..=MAX_GOALS {
+        for y in 0..=MAX_GOALS {
+            let p = poisson(x, scored) * poisson(y, conceded);
+            if x > y {
+                win += p;
+            } else if x == y {
+                draw += p;
+            }
+        }
+    }
+    3.0 * win + draw
+}
+
+fn expected_points(row: &TableRow, points_per_game: f64, model: Model) -> f64 {
+    // the points a team's goals for and against are worth under the model.
+    // the Pythagorean share of goals, GF^k / (GF^k + GA^k), is turned into points by giving a team that share of the points
+    // on offer in its games, where each game is worth the season's average (less than 3, since draws only give out 2).
+    let played = row.played as f64;
+    match model {
+        Model::Pythagorean(exponent) => {
+            let share = (row.goals_for as f64).powf(exponent) / ((row.goals_for as f64).powf(exponent) + (row.goals_against as f64).powf(exponent));
+            share * points_per_game * played
+        },
+        Model::Poisson => poisson_points(row.goals_for as f64 / played, row.goals_against as f64 / played) * played,
+    }
+}
+
+fn season_table(games: &[Game], season: usize) -> (Vec<TableRow>, f64) {
+    // the season's table and the average points given out per game.
+    let table = league_table(games, season);
+    let season_games = games.iter().filter(|g| g.season == season).count() as f64;
+    let total_points: usize = table.iter().map(|r| r.points).sum();
+    (table, total_points as f64 / season_games)
+}
+
+pub fn luck_table(games: &[Game], season: usize, model: Model) -> Vec<LuckRow> {
+    // every team's actual and expected points for a season, luckiest first.
+    let (table, points_per_game) = season_table(games, season);
+    let mut rows: Vec<LuckRow> = table
+        .iter()
+        .map(|row| LuckRow {
+            season,
+            team: row.team.clone(),
+            played: row.played,
+            goals_for: row.goals_for,
+            goals_against: row.goals_against,
+            points: row.points,
+            expected_points: expected_points(row, points_per_game, model),
+        })
+        .collect();
+    rows.sort_by(|a, b| b.difference().total_cmp(&a.difference()));
+    rows
+}
+
+pub fn fit_exponent(games: &[Game], seasons: &[usize]) -> f64 {
+    // the Pythagorean exponent that best fits the seasons, by least squares over a grid from 0.5 to 3.
+    let tables: Vec<(Vec<TableRow>, f64)> = seasons.iter().map(|s| season_table(games, *s)).collect();
+    let mut best = (f64::MAX, 0.0);
+    for step in 50..=300 {
+        let exponent = step as f64 / 100.0;
+        let mut error = 0.0;
+        for (table, points_per_game) in &tables {
+            for row in table {
+                error += (row.points as f64 - expected_points(row, *points_per_game, Model::Pythagorean(exponent))).powi(2);
+            }
+        }
+        if error < best.0 {
+            best = (error, exponent);
+        }
+    }
+    best.1
+}
+
+pub fn regress_to_mean(games: &[Game], seasons: &[usize], model: Model, threshold: f64) -> RegressionToMean {
+    // pairs each team-season's points above expectation with the same team's the next season.
+    let tables: Vec<Vec<LuckRow>> = seasons.iter().map(|s| luck_table(games, *s, model)).collect();
+    let mut pairs = Vec::new();
+    for (this, next) in tables.iter().zip(tables.iter().skip(1)) {
+        for row in this {
+            if let Some(following) = next.iter().find(|r| r.team == row.team && r.season == row.season + 1) {
+                pairs.push((row.difference(), following.difference()));
+            }
+        }
+    }
+    let over: Vec<f64> = pairs.iter().filter(|p| p.0 >= threshold).map(|p| p.1).collect();
+    let under: Vec<f64> = pairs.iter().filter(|p| p.0 <= -threshold).map(|p| p.1).collect();
+    let mean = |values: &[f64]| if values.is_empty() { 0.0 } else { values.iter().sum::<f64>() / values.len() as f64 };
+    RegressionToMean {
+        pairs: pairs.len(),
+        correlation: crate::stats::correlation(&pairs),
+        threshold,
+        overperformers: over.len(),
+        overperformers_next: mean(&over),
+        underperformers: under.len(),
+        underperformers_next: mean(&under),
+    }
+}
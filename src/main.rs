@@ -2,42 +2,79 @@ use std::error::Error;
 use std::collections::HashSet;
 use std::io;
 mod game;
-use crate::game::Game;
+use crate::game::{DataFrame, Game, League};
 mod wins;
 use wins::*;
+mod advantage;
+mod charts;
+mod calendar;
+mod records;
+use records::Ranking;
+mod standings;
+mod summary;
+mod report;
+mod server;
+mod import;
+mod halftime;
+mod matchstats;
+mod ratings;
+mod database;
+mod columnar;
+mod fixtures;
+mod strength;
+mod stats;
+mod luck;
+mod predict;
+mod calibration;
+mod changepoint;
+mod regression;
+mod compare;
 use crate::game::MyError;
 use plotters::prelude::*;
 extern crate plotters;
 
-fn team_win_rate(games: &Vec<Game>, team: &String, seasons: &Vec<usize>) -> f64 {
-    // for a given team and range of seasons, find the % of games they won based on the number of games they played.
+fn team_wins(games: &[Game], team: &String, seasons: &[usize]) -> (usize, usize) {
+    // for a given team and range of seasons, count the games they won and the games they played.
     let mut appearances = 0;
     let mut wins = 0;
-    let mut percent = 0.0;
     for season in seasons.iter() {
         for game in games.iter() {
             if *season == game.season {
                 // home and away teams are classed differently, so handle with two conditionals.
                 if *team == game.home {
                     appearances += 1;
-                    if game.result == "H".to_string() {
+                    if game.result == "H" {
                         wins += 1;
                     }
+                }
                 if *team == game.away {
                     appearances += 1;
-                    if game.result == "A".to_string() {
+                    if game.result == "A" {
                         wins += 1
                     }
                 }
-                }
             }
         }
-        percent = (wins as f64)/(appearances as f64).round();
     }
-    percent * 100.0
+    (wins, appearances)
+}
+
+fn team_win_rate(games: &[Game], team: &String, seasons: &[usize]) -> f64 {
+    // for a given team and range of seasons, find the % of games they won based on the number of games they played.
+    let (wins, appearances) = team_wins(games, team, seasons);
+    if appearances == 0 {
+        return 0.0;
+    }
+    (wins as f64)/(appearances as f64) * 100.0
 }
 
-fn top_percent(games: &Vec<Game>, teams: &HashSet<String>, n: usize, seasons: &Vec<usize>) -> Vec<(String, f64)> { 
+fn team_win_estimate(games: &[Game], team: &String, seasons: &[usize]) -> stats::Estimate {
+    // team_win_rate with a Wilson confidence interval.
+    let (wins, appearances) = team_wins(games, team, seasons);
+    stats::wilson(wins, appearances).scaled(100.0)
+}
+
+fn top_percent(games: &[Game], teams: &HashSet<String>, n: usize, seasons: &[usize]) -> Vec<(String, f64)> { 
     // for a given set of teams and seasons, return the top n teams based on win percentage.
     let mut team_pct: Vec<(String, f64)> = Vec::new();
     for team in teams.iter() {
@@ -51,7 +88,7 @@ fn top_percent(games: &Vec<Game>, teams: &HashSet<String>, n: usize, seasons: &V
     top_pct
 }
 
-fn team_seasons(games: &Vec<Game>, team: &String, seasons: &Vec<usize>) -> (Vec<usize>, usize) {
+fn team_seasons(games: &[Game], team: &String, seasons: &[usize]) -> (Vec<usize>, usize) {
     // based on a team and a range of seasons, count all the seasons in that range that they appeared in the league.
     let mut seasons_list = Vec::new();
     for season in seasons.iter() {
@@ -68,7 +105,7 @@ fn team_seasons(games: &Vec<Game>, team: &String, seasons: &Vec<usize>) -> (Vec<
     (seasons_list.clone(), seasons_list.len())
 }
 
-fn top_appearances(games: &Vec<Game>, teams: &HashSet<String>, seasons: &Vec<usize>, n: usize) -> Vec<(String, usize)> {
+fn top_appearances(games: &[Game], teams: &HashSet<String>, seasons: &[usize], n: usize) -> Vec<(String, usize)> {
     // similar to top_pct - returns the top n teams by seasons appeared in the league.
     let mut team_appearances: Vec<(String, usize)> = Vec::new();
     for team in teams.iter() {
@@ -82,7 +119,7 @@ fn top_appearances(games: &Vec<Game>, teams: &HashSet<String>, seasons: &Vec<usi
     top_app
 }
 
-fn goal_avg(games: &Vec<Game>, season: usize) -> f64 {
+fn goal_avg(games: &[Game], season: usize) -> f64 {
     // calculates the average number of goals scored per game in a season.
     let mut goal_total = 0;
     let mut game_count = 0;
@@ -96,33 +133,31 @@ fn goal_avg(games: &Vec<Game>, season: usize) -> f64 {
     return (goal_total as f64)/(game_count as f64);
 } 
 
-fn greatest_interval(games: &Vec<Game>, team: &String, seasons: &Vec<usize>) -> Game {
-    // calculates the greatest game-winning interval for a given team in a given range of seasons.
-    // returns a Game instance with all the information about that game.
-    let mut biggest_game = games[0].clone(); // set the biggest game as the first one by default
-    let mut goal_differential = 0;
-    for game in games {
-        for season in seasons {
-            if game.season == *season {
-                if game.home == *team && game.result == "H" {
-                    if game.home_goals - game.away_goals > goal_differential {
-                        biggest_game = game.clone();
-                        goal_differential = game.home_goals - game.away_goals;
-                    }
-                }
-                if game.away == *team && game.result == "A" {
-                    if game.away_goals - game.home_goals > goal_differential {
-                        biggest_game = game.clone();
-                        goal_differential = game.away_goals - game.home_goals;
-                    }
-                }
-            }
-        }
-    }
-    biggest_game
+fn goal_avg_estimate(games: &[Game], season: usize) -> stats::Estimate {
+    // goal_avg with a bootstrap confidence interval, since goals per game aren't a simple proportion.
+    // the resampling is seeded with the season, so the interval is the same wherever it's shown.
+    let goals: Vec<f64> = games.iter().filter(|g| g.season == season).map(|g| (g.home_goals + g.away_goals) as f64).collect();
+    stats::bootstrap_mean(&goals, &mut stats::Rng::new(season as u64))
+}
+
+fn greatest_interval(games: &[Game], team: &String, seasons: &[usize]) -> Option<Game> {
+    // finds the game with the greatest winning interval for a given team in a given range of seasons.
+    // returns None if the team didn't win a game in those seasons.
+    records::leaderboard(games, Ranking::BiggestWins, Some(team), seasons, 1).pop()
 }
 
-fn user_choice(games: &Vec<Game>, all_seasons: &Vec<usize>, all_teams: &HashSet<String>) -> Result<(), Box<dyn Error>>{
+fn head_to_head<'a>(games: &'a [Game], team_a: &String, team_b: &String, seasons: &[usize]) -> Vec<&'a Game> {
+    // every game between two teams in a range of seasons, at either ground, in date order.
+    let mut meetings: Vec<&Game> = games
+        .iter()
+        .filter(|g| seasons.contains(&g.season))
+        .filter(|g| (g.home == *team_a && g.away == *team_b) || (g.home == *team_b && g.away == *team_a))
+        .collect();
+    meetings.sort_by_key(|g| g.date);
+    meetings
+}
+
+fn user_choice(games: &[Game], all_seasons: &[usize], all_teams: &HashSet<String>, league: &str) -> Result<(), Box<dyn Error>>{
     // takes in three user inputs: a team name, a starting season, and an ending season
     // returns an empty Result enum if successful; prints information about that team and season range.
     let mut team_input = String::new();
@@ -131,12 +166,15 @@ fn user_choice(games: &Vec<Game>, all_seasons: &Vec<usize>, all_teams: &HashSet<
     team_input = team_input.trim().to_string();
 
     if !all_teams.contains(&team_input) {
-        println!("Please enter a valid team. Here is the full list of Premier League teams: {:?}", all_teams);
+        println!("Please enter a valid team. Here is the full list of {} teams: {:?}", league, all_teams);
         return Err(Box::new(MyError("You did not enter a valid team name".to_string())));
     }
 
+    let (Some(first_season), Some(last_season)) = (all_seasons.first(), all_seasons.last()) else {
+        return Err(Box::new(MyError("There are no seasons to choose from".to_string())));
+    };
     let mut season_start_input_string = String::new();
-    println!("Enter a starting season from 1993 to 2023: ");
+    println!("Enter a starting season from {} to {}: ", first_season, last_season);
     io::stdin().read_line(&mut season_start_input_string).expect("Failed to read line");
     season_start_input_string = season_start_input_string.trim().to_string();
     let season_start_input = match season_start_input_string.parse::<usize>() { // ensure that input is a valid digit
@@ -147,7 +185,7 @@ fn user_choice(games: &Vec<Game>, all_seasons: &Vec<usize>, all_teams: &HashSet<
         },
     };
     let mut season_end_input_string = String::new();
-    println!("Enter an ending season from 1993 to 2023: ");
+    println!("Enter an ending season from {} to {}: ", first_season, last_season);
     io::stdin().read_line(&mut season_end_input_string).expect("Failed to read line");
     season_end_input_string = season_end_input_string.trim().to_string();
     let season_end_input = match season_end_input_string.parse::<usize>() {
@@ -162,7 +200,7 @@ fn user_choice(games: &Vec<Game>, all_seasons: &Vec<usize>, all_teams: &HashSet<
         return Err(Box::new(MyError("There was an error".to_string())));
     }
     if !all_seasons.contains(&season_start_input) | !all_seasons.contains(&season_end_input) {
-        println!("One or more of your seasons is not in the valid range of 1993 to 2023.");
+        println!("One or more of your seasons is not in the valid range of {} to {}.", first_season, last_season);
         return Err(Box::new(MyError("There was an error".to_string())));
     }
     let chosen_seasons: Vec<usize> = (season_start_input..=season_end_input).collect();
@@ -180,33 +218,285 @@ fn user_choice(games: &Vec<Game>, all_seasons: &Vec<usize>, all_teams: &HashSet<
         }
     }
     if total_games == 0 {
-        println!("This team did not play in the Premier League for any of the time you specified.");
-        println!("{} played in the PL during the following seasons: {:?}", team_input, team_seasons(games, &team_input, all_seasons).0);
+        println!("This team did not play in the {} for any of the time you specified.", league);
+        println!("{} played in the {} during the following seasons: {:?}", team_input, league, team_seasons(games, &team_input, all_seasons).0);
         return Err(Box::new(MyError("There was an error".to_string())));
     }
     let (team_chosen_seasons, team_seasons_count) = team_seasons(games, &team_input, &chosen_seasons);
     println!();
     println!("{} played in {} seasons over that interval: {:?}", team_input, team_seasons_count, team_chosen_seasons);
     println!();
-    let goal_int = greatest_interval(&games, &team_input, &chosen_seasons);
-    println!("The biggest win interval for {} in those seasons was in the below game:", team_input);
-    goal_int.print();
-    let win_rate = team_win_rate(&games, &team_input, &chosen_seasons);
-    println!("The {} win rate for the {} to {} seasons is {:.4}%.", team_input, chosen_seasons[0], chosen_seasons.last().unwrap(), win_rate);
+    match greatest_interval(games, &team_input, &chosen_seasons) {
+        Some(goal_int) => {
+            println!("The biggest win interval for {} in those seasons was in the below game:", team_input);
+            goal_int.print();
+        },
+        None => println!("{} did not win a game in those seasons.", team_input),
+    }
+    let defeats = records::leaderboard(games, Ranking::HeaviestDefeats, Some(&team_input), &chosen_seasons, 3);
+    if !defeats.is_empty() {
+        println!("The {} heaviest defeats for {} in those seasons:", defeats.len(), team_input);
+        for game in &defeats {
+            game.print();
+        }
+    }
+    let win_rate = team_win_estimate(games, &team_input, &chosen_seasons);
+    println!("The {} win rate for the {} to {} seasons is {:.4}%, with a 95% confidence interval of {:.4}% to {:.4}% (standard error {:.4}%).", team_input, chosen_seasons[0], chosen_seasons.last().unwrap(), win_rate.value, win_rate.lower, win_rate.upper, win_rate.standard_error);
+    if let Some(adjusted) = strength::adjusted_win_rate(games, &team_input, &chosen_seasons) {
+        println!("Adjusted for the strength of the opponents they faced, that win rate is {:.4}%.", adjusted);
+    }
+    if let Some((score_rate, adjusted)) = strength::adjusted_score_rate(games, &team_input, &chosen_seasons) {
+        println!("Counting a draw as half a win, {} scored {:.4}% of the maximum, or {:.4}% adjusted for their opponents.", team_input, score_rate, adjusted);
+    }
+    let drawing_area = BitMapBackend::new("team_advantage.png", (640, 480)).into_drawing_area();
+    charts::home_edge_chart(&drawing_area, games, Some(&team_input), &chosen_seasons)?;
+    println!("The home advantage trend for {} over those seasons has been drawn to team_advantage.png.", team_input);
+    let matrix = halftime::transitions(games, Some(&team_input), &chosen_seasons);
+    if matrix.games() > 0 {
+        matrix.print();
+        println!("{} took {} points from losing positions at half-time and dropped {} from winning ones.", team_input, matrix.points_won_from_losing(), matrix.points_dropped_from_winning());
+    }
     return Ok(());
 }
 
 fn main() {
-    let mut df = game::DataFrame::new();
-    let games = df.read_csv("pl_matches.csv").unwrap();
-    let all_seasons: Vec<usize> = (1993..=2023).collect();
+    let mut args: Vec<String> = std::env::args().collect();
+    let league = League::premier_league();
+    let mut sources = vec![(league.clone(), "pl_matches.csv".to_string())];
+    while let Some(i) = args.iter().position(|a| a == "--league") { // `--league "La Liga=laliga.csv"` loads another league's csv alongside the Premier League
+        args.remove(i);
+        let spec = if i < args.len() { args.remove(i) } else { String::new() };
+        match spec.split_once('=') {
+            Some((name, path)) => sources.push((League::from_name(name), path.to_string())),
+            None => {
+                println!("Give each extra league as --league \"Name=path.csv\".");
+                return;
+            },
+        }
+    }
+    if args.len() > 1 && args[1] == "rebuild-cache" { // `cargo run -- rebuild-cache` re-reads each csv and rewrites its .cache file
+        for (source_league, path) in &sources {
+            let count = DataFrame::rebuild_cache(path, source_league.clone()).unwrap();
+            println!("Cached {} {} games to {}.", count, source_league.name, game::cache_path(path));
+        }
+        return;
+    }
+    if args.len() > 3 && args[1] == "import" { // `cargo run -- import pl_matches.csv E0.csv` adds the games from football-data.co.uk files to a csv in our layout
+        let mut merged = DataFrame::new();
+        if std::path::Path::new(&args[2]).exists() {
+            merged.read_csv(&args[2], league.clone()).unwrap();
+        }
+        let before = merged.games().len();
+        for path in &args[3..] {
+            merged.read_csv(path, league.clone()).unwrap();
+        }
+        let mut seen = HashSet::new();
+        let mut imported = Vec::new();
+        let mut existing = 0;
+        for (i, game) in merged.games().iter().enumerate() {
+            if seen.insert((game.date, game.home.clone(), game.away.clone())) { // games already in the csv are kept once, in their original order
+                imported.push(game.clone());
+                if i < before {
+                    existing += 1;
+                }
+            }
+        }
+        imported[existing..].sort_by_key(|g| (g.season, g.week, g.date));
+        game::write_csv(&args[2], &imported).unwrap();
+        println!("Added {} {} games to {}, which now holds {}.", imported.len() - existing, league.name, args[2], imported.len());
+        return;
+    }
+    let mut df = DataFrame::new();
+    if let Some(i) = args.iter().position(|a| a == "--db") { // `--db pl.db` loads every league from a database written by export-db instead of the csvs
+        args.remove(i);
+        let path = if i < args.len() { args.remove(i) } else { "pl.db".to_string() };
+        for db_league in database::leagues(&path).unwrap() {
+            df.read_sqlite(&path, db_league).unwrap();
+        }
+    } else if let Some(i) = args.iter().position(|a| a == "--parquet" || a == "--arrow") { // `--parquet pl.parquet` or `--arrow pl.arrow` loads a file written by export-parquet or export-arrow
+        let flag = args.remove(i);
+        let path = if i < args.len() { args.remove(i) } else { String::new() };
+        if flag == "--parquet" { df.read_parquet(&path).unwrap(); } else { df.read_ipc(&path).unwrap(); }
+    } else {
+        for (source_league, path) in &sources {
+            df.read_cached(path, source_league.clone()).unwrap(); // uses the cache when it matches the csv
+        }
+    }
+    if args.len() > 1 && args[1] == "export-db" { // `cargo run -- export-db pl.db` saves the games, standings and ratings to SQLite
+        let path = args.get(2).map(|p| p.as_str()).unwrap_or("pl.db");
+        match database::write_database(&df, path) {
+            Ok(()) => println!("Saved {} games from {} league(s) to {}.", df.games().len(), df.leagues().len(), path),
+            Err(e) => println!("{}.", e),
+        }
+        return;
+    }
+    if args.len() > 1 && (args[1] == "export-parquet" || args[1] == "export-arrow") { // `cargo run -- export-parquet pl.parquet` writes the games with a typed schema for Polars or pandas
+        let parquet = args[1] == "export-parquet";
+        let path = args.get(2).cloned().unwrap_or_else(|| if parquet { "pl.parquet" } else { "pl.arrow" }.to_string());
+        if parquet { columnar::write_parquet(df.games(), &path).unwrap(); } else { columnar::write_ipc(df.games(), &path).unwrap(); }
+        println!("Saved {} games from {} league(s) to {}.", df.games().len(), df.leagues().len(), path);
+        return;
+    }
+    let games = df.league_games(&league.name); // the main analysis is on the Premier League; other leagues are compared at the end
+    let all_seasons = game::seasons_in(games);
+    let (Some(&first_season), Some(&last_season)) = (all_seasons.first(), all_seasons.last()) else {
+        println!("No {} games were loaded, so there is nothing to analyse. Load them from its csv, or pass a --db, --parquet or --arrow file that includes them.", league.name);
+        return;
+    };
     let mut all_teams = HashSet::new();
     for game in games.iter() {
         all_teams.insert(game.home.clone());
     }
+    if args.len() > 2 && args[1] == "season" { // `cargo run -- season 2021` prints the report for one season and stops there
+        match args[2].parse::<usize>().ok().and_then(|szn| summary::season_summary(games, szn, &all_seasons)) {
+            Some(report) => report.print(),
+            None => println!("Enter a season from {} to {} in digits.", first_season, last_season),
+        }
+        return;
+    }
+    if args.len() > 2 && args[1] == "fixtures" { // `cargo run -- fixtures 2021` generates a balanced fixture list for that season's teams and compares it with the real one
+        let Some(season) = args[2].parse::<usize>().ok().filter(|szn| all_seasons.contains(szn)) else {
+            println!("Enter a season from {} to {} in digits.", first_season, last_season);
+            return;
+        };
+        let mut teams: Vec<String> = standings::league_table(games, season).into_iter().map(|r| r.team).collect();
+        teams.sort();
+        let generated = match args.get(3).and_then(|w| w.parse::<usize>().ok()) { // an optional matchweek count, e.g. 57 for three rounds of 20 teams
+            Some(weeks) => fixtures::fixture_list(&teams, weeks),
+            None => fixtures::double_round_robin(&teams),
+        };
+        for fixture in &generated {
+            println!("Week {}: {} v {}", fixture.week, fixture.home, fixture.away);
+        }
+        println!();
+        for stats in [fixtures::schedule_stats("Generated", &generated), fixtures::schedule_stats(&format!("The real {} season", season), &fixtures::season_fixtures(games, season))] {
+            println!("{}: {} fixtures over {} matchweeks, {} home/away breaks (at most {} for one team), longest run at home or away {}, meetings {:.1} weeks apart on average and {} at the closest", stats.label, stats.fixtures, stats.weeks, stats.breaks, stats.most_breaks, stats.longest_run, stats.mean_meeting_gap, stats.shortest_meeting_gap);
+        }
+        return;
+    }
+    if args.len() > 1 && args[1] == "backtest" { // `cargo run -- backtest` predicts every game from the ones before it and scores each predictor by season
+        println!("Each game is predicted from the games played before its date. Lower log-loss and Brier scores are better; a third on each result scores {:.3} and {:.3}.", 3f64.ln(), 2.0 / 3.0);
+        let drawing_area = BitMapBackend::new("calibration.png", (1280, 960)).into_drawing_area(); // one reliability chart per predictor
+        let panels = drawing_area.split_evenly((2, 2));
+        for (mut predictor, panel) in predict::predictors().into_iter().zip(panels.iter()) {
+            let predictions = predict::walk_forward(games, predictor.as_mut());
+            println!();
+            println!("{}:", predictor.name());
+            for row in predict::score_by_season(&predictions) {
+                println!("{}: log-loss {:.4}, Brier {:.4}, {:.2}% called right over {} games", row.label, row.log_loss, row.brier, row.accuracy, row.games);
+            }
+            let overall = predict::score("Overall".to_string(), &predictions.iter().collect::<Vec<_>>());
+            println!("{}: log-loss {:.4}, Brier {:.4}, {:.2}% called right over {} games", overall.label, overall.log_loss, overall.brier, overall.accuracy, overall.games);
+            let curves = calibration::calibration(&predictions, 10);
+            for curve in &curves {
+                println!("{} calibration: expected calibration error {:.4}", curve.outcome, curve.expected_calibration_error);
+                for bin in &curve.bins {
+                    println!("  predicted {:.0}-{:.0}%: {} games, {:.1}% predicted on average, {:.1}% happened", bin.lower * 100.0, bin.upper * 100.0, bin.predictions, bin.mean_predicted * 100.0, bin.observed * 100.0);
+                }
+            }
+            charts::reliability_chart(panel, &predictor.name(), &curves).unwrap();
+        }
+        println!();
+        println!("The reliability curves have been drawn to calibration.png.");
+        return;
+    }
+    if args.len() > 1 && args[1] == "regression" { // `cargo run -- regression` fits the result and goal models and prints every coefficient
+        for fit in [regression::ordinal_logit(games), regression::multinomial_logit(games), regression::poisson_goals(games)] {
+            match fit {
+                Ok(fit) => fit.print(),
+                Err(e) => println!("{}.", e),
+            }
+            println!();
+        }
+        return;
+    }
+    if args.len() > 4 && args[1] == "compare" { // `cargo run -- compare 2010 2023 Arsenal Chelsea "Manchester City"` puts two or more teams side by side over those seasons
+        let (Ok(start), Ok(end)) = (args[2].parse::<usize>(), args[3].parse::<usize>()) else {
+            println!("Enter the first and last seasons in digits, then two or more teams.");
+            return;
+        };
+        let seasons: Vec<usize> = all_seasons.iter().copied().filter(|s| *s >= start && *s <= end).collect();
+        let teams: Vec<String> = args[4..].to_vec();
+        if let Some(unknown) = teams.iter().find(|t| !all_teams.contains(*t)) {
+            println!("{} isn't a {} team. Here is the full list: {:?}", unknown, league.name, all_teams);
+            return;
+        }
+        if seasons.is_empty() || teams.len() < 2 {
+            println!("Enter a season range from {} to {} and at least two teams.", first_season, last_season);
+            return;
+        }
+        let finish = |position: Option<usize>| position.map(|p| p.to_string()).unwrap_or("-".to_string());
+        println!("{} to {}:", seasons[0], seasons[seasons.len() - 1]);
+        println!("{:<24} {:>7} {:>6} {:>8} {:>6} {:>6} {:>6} {:>8} {:>8} {:>5} {:>6} {:>7}", "Team", "Seasons", "Played", "Win rate", "PPG", "GF/G", "GA/G", "Home PPG", "Away PPG", "Best", "Worst", "Average");
+        for row in compare::compare(games, &teams, &seasons) {
+            let average = row.average_finish.map(|a| format!("{:.1}", a)).unwrap_or("-".to_string());
+            println!("{:<24} {:>7} {:>6} {:>7.2}% {:>6.3} {:>6.3} {:>6.3} {:>8.3} {:>8.3} {:>5} {:>6} {:>7}", row.team, row.seasons, row.played, row.win_rate, row.points_per_game, row.goals_for_per_game, row.goals_against_per_game, row.home_points_per_game, row.away_points_per_game, finish(row.best_finish), finish(row.worst_finish), average);
+        }
+        println!();
+        println!("Head to head:");
+        for record in compare::head_to_heads(games, &teams, &seasons) {
+            println!("{} v {}: played {}, {} won {}, drew {}, {} won {}, goals {}-{}", record.team, record.opponent, record.played, record.team, record.won, record.drawn, record.opponent, record.lost, record.goals_for, record.goals_against);
+        }
+        let drawing_area = BitMapBackend::new("team_comparison.png", (1024, 576)).into_drawing_area();
+        charts::comparison_chart(&drawing_area, games, &teams, &seasons).unwrap();
+        println!();
+        println!("Their points per game season by season have been drawn to team_comparison.png.");
+        return;
+    }
+    if args.len() > 3 && (args[1] == "positions" || args[1] == "season-positions") { // `cargo run -- positions 2000 2023 Arsenal Chelsea` charts final positions each season; `cargo run -- season-positions 2023 Arsenal Chelsea` charts them after each matchweek
+        let by_season = args[1] == "positions";
+        let team_start = if by_season { 4 } else { 3 };
+        let start = args[2].parse::<usize>().ok();
+        let end = if by_season { args[3].parse::<usize>().ok() } else { start };
+        let (Some(start), Some(end)) = (start, end) else {
+            println!("Enter the season{} in digits, then one or more teams.", if by_season { "s" } else { "" });
+            return;
+        };
+        let seasons: Vec<usize> = all_seasons.iter().copied().filter(|s| *s >= start && *s <= end).collect();
+        let teams: Vec<String> = args[team_start.min(args.len())..].to_vec();
+        if let Some(unknown) = teams.iter().find(|t| !all_teams.contains(*t)) {
+            println!("{} isn't a {} team. Here is the full list: {:?}", unknown, league.name, all_teams);
+            return;
+        }
+        if seasons.is_empty() || teams.is_empty() {
+            println!("Enter seasons from {} to {} and at least one team.", first_season, last_season);
+            return;
+        }
+        let lines: Vec<(String, Vec<(usize, usize)>)> = teams
+            .iter()
+            .map(|team| (team.clone(), if by_season { compare::finishes(games, team, &seasons) } else { standings::positions_by_week(games, start, team) }))
+            .collect();
+        for (team, points) in &lines {
+            let positions: Vec<String> = points.iter().map(|(x, position)| format!("{}{}: {}", if by_season { "" } else { "week " }, x, position)).collect();
+            println!("{}: {}", team, if positions.is_empty() { "not in the league".to_string() } else { positions.join(", ") });
+        }
+        let places = seasons.iter().map(|s| standings::league_table(games, *s).len()).max().unwrap_or(20);
+        let (path, title, x_desc) = if by_season {
+            ("positions.png", format!("Final league positions - {} to {}", seasons[0], seasons[seasons.len() - 1]), "Season")
+        } else {
+            ("season_positions.png", format!("League positions by matchweek - {}", start), "Matchweek")
+        };
+        let drawing_area = BitMapBackend::new(path, (1024, 576)).into_drawing_area();
+        match charts::bump_chart(&drawing_area, &title, x_desc, &lines, places) {
+            Ok(()) => println!("The positions have been drawn to {}.", path),
+            Err(e) => println!("Nothing to draw: {}", e),
+        }
+        return;
+    }
+    if args.len() > 1 && args[1] == "serve" { // `cargo run -- serve 8080` answers JSON requests on localhost until stopped
+        let port = args.get(2).and_then(|p| p.parse::<u16>().ok()).unwrap_or(8080);
+        server::serve(games, &all_teams, &all_seasons, port).unwrap();
+        return;
+    }
+    if args.len() > 1 && args[1] == "report" { // `cargo run -- report` writes everything to a single HTML page
+        let path = args.get(2).map(|p| p.as_str()).unwrap_or("report.html");
+        report::write_html_report(games, &all_teams, &all_seasons, &league.name, path).unwrap();
+        println!("The report has been written to {}.", path);
+        return;
+    }
     let rankings = 10; // get the top 10 in both success categories
-    println!("Over {} seasons, a total of {} teams have competed in the Premier League.", all_seasons.len(), all_teams.len());
-    let top10_pct = top_percent(&games, &all_teams, rankings, &all_seasons);
+    println!("Over {} seasons, a total of {} teams have competed in the {}.", all_seasons.len(), all_teams.len(), league.name);
+    let top10_pct = top_percent(games, &all_teams, rankings, &all_seasons);
     println!();
     println!("Most successful teams by win percentage:");
     for (i, (team, pct)) in top10_pct.iter().enumerate() {
@@ -214,82 +504,50 @@ fn main() {
     }
     println!();
     println!("Most successful teams by number of seasons:");
-    let top10_app = top_appearances(&games, &all_teams, &all_seasons, rankings);
+    let top10_app = top_appearances(games, &all_teams, &all_seasons, rankings);
     for (i, (team, season_apps)) in top10_app.iter().enumerate() {
-        println!("{}: {} with {} total seasons in the PL", (i+1), team, season_apps)
+        println!("{}: {} with {} total seasons in the {}", (i+1), team, season_apps, league.name)
     }
 
     let mut home_advantages: Vec<(usize, f64)> = Vec::new();
     let mut avg_home = 0.0;
     let mut avg_away = 0.0;
     for szn in &all_seasons {
-        home_advantages.push((*szn, home_pct(&games, *szn))); // collect each season's average home win rate
-        avg_home += home_pct(&games, *szn);
-        avg_away += away_pct(&games, *szn);
+        home_advantages.push((*szn, home_pct(games, *szn))); // collect each season's average home win rate
+        avg_home += home_pct(games, *szn);
+        avg_away += away_pct(games, *szn);
     }
 
     avg_home = avg_home/(all_seasons.len() as f64);
     avg_away = avg_away/(all_seasons.len() as f64);
     let avg_diff = avg_home - avg_away;
-    let mut worst_szn = 0;
-    let mut worst_adv = 100.0;
-    let mut second_worst_szn = 0;
-    let mut diff = 0.0;
-    for (szn, adv) in &home_advantages {
-        if *adv < worst_adv {
-            diff = worst_adv - adv;
-            worst_adv = *adv;
-            second_worst_szn = worst_szn;
-            worst_szn = *szn // season with least amount of home advantage (difference between home and away win rate)
-        }
-    }
-    let mut x_values: [f64; 31] = [1993.0; 31];
-    for i in 0..31 {
-        x_values[i] += i as f64;
-    } // update array in place so each x value is a season
+    home_advantages.sort_by(|a, b| a.1.total_cmp(&b.1)); // lowest home win rate first
+    let Some(&(worst_szn, worst_adv)) = home_advantages.first() else { // season with least amount of home advantage
+        return;
+    };
     let drawing_area = BitMapBackend::new("all_time_rates.png", (640, 480)).into_drawing_area();
-    drawing_area.fill(&WHITE).unwrap();
-    let mut chart_builder = ChartBuilder::on(&drawing_area)
-        .x_label_area_size(35)
-        .y_label_area_size(50)
-        .caption("Home, Away, and Draw results - 1993-2023", ("sans-serif", 20).into_font())
-        .margin(5)
-        .build_cartesian_2d(1993.0..2023.0, 10.0..60.0).unwrap();
-    chart_builder.configure_mesh()
-        .y_desc("Result rates")
-        .x_desc("Season")
-        .axis_desc_style(("sans-serif", 15))
-        .draw().unwrap();
-    chart_builder.draw_series(LineSeries::new(x_values.map(|x | (x, home_pct(&games, x as usize))), BLACK)).unwrap() // for each season, compute the home win rate
-        .label("Home win rate")
-        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &BLACK)); // add legend
-    chart_builder.draw_series(LineSeries::new(x_values.map(|x | (x, away_pct(&games, x as usize))), RED)).unwrap() // compute home, win, and away rate separately
-        .label("Away win rate")
-        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &RED));
-    chart_builder.draw_series(LineSeries::new(x_values.map(|x | (x, draw_pct(&games, x as usize))), BLUE)).unwrap()
-        .label("Draw rate")
-        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &BLUE));
-    chart_builder.configure_series_labels()
-        .position(SeriesLabelPosition::LowerRight)
-        .border_style(&BLACK)
-        .background_style(&WHITE.mix(0.8))
-        .draw()
-        .unwrap();
+    charts::result_rates_chart(&drawing_area, games, &all_seasons).unwrap();
     println!();
-    println!("The average home win-rate in the Premier League across all seasons is {:.3}%, compared to an away win-rate of {:.3}%.", avg_home, avg_away);
-    println!("The season with the lowest home-win rate was {} with a home-win rate of {:.3}%, which is {:.3}% worse than the second-worst season of {}.", worst_szn, worst_adv, diff, second_worst_szn);
-    let this_draw = draw_pct(&games, worst_szn.clone());
-    let this_away = away_pct(&games, worst_szn.clone());
+    println!("The average home win-rate in the {} across all seasons is {:.3}%, compared to an away win-rate of {:.3}%.", league.name, avg_home, avg_away);
+    match home_advantages.get(1) {
+        Some((second_worst_szn, second_worst_adv)) => println!("The season with the lowest home-win rate was {} with a home-win rate of {:.3}%, which is {:.3}% worse than the second-worst season of {}.", worst_szn, worst_adv, second_worst_adv - worst_adv, second_worst_szn),
+        None => println!("The only season loaded, {}, had a home-win rate of {:.3}%.", worst_szn, worst_adv),
+    }
+    let this_draw = draw_pct(games, worst_szn.clone());
+    let this_away = away_pct(games, worst_szn.clone());
+    let worst_estimate = home_estimate(games, worst_szn);
+    let verdict = if worst_estimate.upper < avg_home { "so it is unlikely to be chance" } else { "so it could be chance" };
+    println!("Over {} games that rate has a 95% interval of {:.3}% to {:.3}% (standard error {:.3}%), which {} the all-time average, {}.", games.iter().filter(|g| g.season == worst_szn).count(), worst_estimate.lower, worst_estimate.upper, worst_estimate.standard_error, if worst_estimate.upper < avg_home { "lies below" } else { "includes" }, verdict);
     println!("In {}, the draw rate was {:.3}% and the away-win rate was {:.3}%.", worst_szn, this_draw, this_away);
     println!("This is a home-away differential of {:.3}%. The average home-away differential across all {} seasons is {:.3}%.", worst_adv - this_away, all_seasons.len(), avg_diff);
     let mut all_goal_avg = 0.0;
     let mut goal_averages = Vec::new();
     for szn in &all_seasons {
-        all_goal_avg += goal_avg(&games, *szn);
-        goal_averages.push((*szn, goal_avg(&games, *szn)));
+        all_goal_avg += goal_avg(games, *szn);
+        goal_averages.push((*szn, goal_avg(games, *szn)));
     }
     all_goal_avg = all_goal_avg/(all_seasons.len() as f64);
-    println!("The average number of goals scored in a PL game is {:.4}.", all_goal_avg);
+    println!("The average number of goals scored in a {} game is {:.4}.", league.name, all_goal_avg);
     let mut most_szn: usize = 0;
     let mut most_goals = 0.0;
     for (szn, goals) in &goal_averages {
@@ -299,49 +557,738 @@ fn main() {
         }
     }
     let drawing_area = BitMapBackend::new("goal_averages.png", (640, 480)).into_drawing_area(); // plot average goal trends year over year
-    drawing_area.fill(&WHITE).unwrap();
-    let mut chart_builder = ChartBuilder::on(&drawing_area)
-        .x_label_area_size(35)
-        .y_label_area_size(50)
-        .caption("Total goals per season - 1993-2023", ("sans-serif", 20).into_font())
-        .margin(5)
-        .build_cartesian_2d(1993.0..2023.0, 2.0..3.0).unwrap();
-    chart_builder.configure_mesh()
-        .y_desc("Average goals per game")
-        .x_desc("Season")
-        .axis_desc_style(("sans-serif", 15))
-        .draw().unwrap();
-    chart_builder.draw_series(LineSeries::new(x_values.map(|x | (x, goal_avg(&games, x as usize))), MAGENTA)).unwrap()
-        .label("Average goals per game")
-        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], &MAGENTA));
-    chart_builder.configure_series_labels()
-        .position(SeriesLabelPosition::LowerRight)
-        .border_style(&BLACK)
-        .background_style(&WHITE.mix(0.8))
-        .draw()
-        .unwrap();
+    charts::goal_averages_chart(&drawing_area, games, &all_seasons).unwrap();
     println!("The season with the most average goals per game was {} with {:.4} goals per game.", most_szn, most_goals);
+    let most_estimate = goal_avg_estimate(games, most_szn);
+    println!("Bootstrapping its games gives {} goals per game, {} the all-time average.", most_estimate.describe(4), if most_estimate.lower > all_goal_avg { "clear of" } else { "not clear of" });
+    println!();
+    let drawing_area = BitMapBackend::new("home_advantage.png", (640, 480)).into_drawing_area(); // league-wide home minus away points per game
+    charts::home_edge_chart(&drawing_area, games, None, &all_seasons).unwrap();
+    println!("Teams that lost the most home advantage behind closed doors (home minus away points per game):");
+    let drops = advantage::edge_drop(games, &all_teams, &all_seasons);
+    for (i, (team, drop)) in drops.iter().take(5).enumerate() {
+        println!("{}: {} lost {:.3} points per game of home edge", (i+1), team, drop)
+    }
+    println!();
+    let window = 10; // matchweeks per rolling window: long enough to smooth out single weeks, short enough to date a change within a season
+    let series = advantage::matchweek_series(games, window);
+    advantage::write_matchweek_csv(&series, "matchweek_advantage.csv").unwrap();
+    charts::matchweek_chart(&series, window, advantage::first_closed_doors_week(games), "matchweek_advantage.png").unwrap();
+    match series.get(window.saturating_sub(1)..).and_then(|full| full.iter().min_by(|a, b| a.home_win_rate.total_cmp(&b.home_win_rate))) { // skip the first few points, which don't have a full window yet
+        Some(lowest) => {
+            println!("Over a rolling {} matchweeks, the home-win rate bottomed out at {:.3}% in week {} of the {} season.", window, lowest.home_win_rate, lowest.week, lowest.season);
+            if let Some(recovered) = series.iter().skip_while(|p| (p.season, p.week) <= (lowest.season, lowest.week)).find(|p| p.home_win_rate >= avg_home) {
+                println!("It first climbed back to the all-time average of {:.3}% in week {} of the {} season.", avg_home, recovered.week, recovered.season);
+            }
+        },
+        None => println!("There are not enough matchweeks for a rolling {}-matchweek window.", window),
+    }
+    println!("The full matchweek series has been written to matchweek_advantage.csv and matchweek_advantage.png.");
     println!();
-    let _ = user_choice(&games, &all_seasons, &all_teams);
+    let season_breaks = changepoint::changepoints(&changepoint::season_series(games, &all_seasons), 1, 999);
+    println!("Significant breaks in the home-minus-away win rate by season: {}", if season_breaks.is_empty() { "none" } else { "" });
+    for point in &season_breaks {
+        println!("{}.", point.describe());
+    }
+    let week_breaks = changepoint::changepoints(&changepoint::matchweek_series(games), window, 199); // fewer shuffles, as the matchweek series is long
+    println!("And by matchweek, with at least {} matchweeks between breaks: {}", window, if week_breaks.is_empty() { "none" } else { "" });
+    for point in &week_breaks {
+        println!("{}.", point.describe());
+    }
+    println!();
+    println!("Controlling for the teams' Elo ratings, the season, the month and each side's rest (run `cargo run -- regression` for every coefficient):");
+    match regression::ordinal_logit(games) { // the fit fails when no games were behind closed doors, as that column is then all zero
+        Ok(ordinal) => if let Some(closed) = ordinal.coefficient("closed doors") {
+            println!("Closed doors moved results towards the away side by {:.3} on the log-odds scale (SE {:.3}, p = {:.4}); the odds of a better result for the home side were {:.0}% of normal.", -closed.estimate, closed.standard_error, closed.p_value(), closed.estimate.exp() * 100.0);
+        },
+        Err(e) => println!("{}.", e),
+    }
+    match regression::poisson_goals(games) {
+        Ok(goals_fit) => if let (Some(home), Some(lost)) = (goals_fit.coefficient("at home"), goals_fit.coefficient("at home × closed doors")) {
+            println!("Home sides normally score {:.1}% more goals than they would away; behind closed doors that edge changed by a factor of {:.3} (SE {:.3} on the log scale, p = {:.4}).", (home.estimate.exp() - 1.0) * 100.0, lost.estimate.exp(), lost.standard_error, lost.p_value());
+        },
+        Err(e) => println!("{}.", e),
+    }
+    println!();
+    for ranking in [Ranking::BiggestWins, Ranking::HighestScoring, Ranking::HighestScoringDraws] {
+        println!("The five {} in {} history:", ranking.describe(), league.name);
+        for game in records::leaderboard(games, ranking, None, &all_seasons, 5) {
+            game.print();
+        }
+    }
+    println!("Results by day of the week:");
+    for day in calendar::by_weekday(games) {
+        println!("{}: {} games, {:.2}% home wins, {:.2}% draws, {:.2}% away wins, {:.3} goals per game", day.label, day.games, day.home_win_rate, day.draw_rate, day.away_win_rate, day.goals_per_game);
+    }
+    println!();
+    println!("Results by month:");
+    for month in calendar::by_month(games) {
+        println!("{}: {} games, {:.2}% home wins, {:.2}% draws, {:.2}% away wins, {:.3} goals per game", month.label, month.games, month.home_win_rate, month.draw_rate, month.away_win_rate, month.goals_per_game);
+    }
+    println!();
+    if let (Some(festive), Some(rest)) = calendar::festive_split(games) {
+        println!("Over the festive period ({} games) the home-win rate is {:.2}% with {:.3} goals per game, compared to {:.2}% and {:.3} for the rest of the season.", festive.games, festive.home_win_rate, festive.goals_per_game, rest.home_win_rate, rest.goals_per_game);
+        println!();
+    }
+    println!("Points per game by days since the team's previous league match:");
+    for (band, appearances, ppg) in calendar::congestion_bands(games) {
+        println!("{}: {:.3} points per game over {} appearances", band, ppg, appearances);
+    }
+    println!("The correlation between the home side's rest advantage and the home goal difference is {:.4}.", calendar::rest_correlation(games));
+    println!();
+    let latest = last_season;
+    let exponent = luck::fit_exponent(games, &all_seasons);
+    for model in [luck::Model::Pythagorean(exponent), luck::Model::Poisson] {
+        println!("Points against the {} expectation from goals for and against:", model.describe());
+        for season in &all_seasons {
+            let table = luck::luck_table(games, *season, model);
+            let (lucky, unlucky) = (&table[0], &table[table.len() - 1]);
+            println!("{}: {} had {} points from an expected {:.1} ({:+.1}), {} had {} from {:.1} ({:+.1})", season, lucky.team, lucky.points, lucky.expected_points, lucky.difference(), unlucky.team, unlucky.points, unlucky.expected_points, unlucky.difference());
+        }
+        let carry = luck::regress_to_mean(games, &all_seasons, model, 5.0);
+        println!("Across {} pairs of consecutive seasons, the correlation between a team's points above expectation one season and the next is {:.3}.", carry.pairs, carry.correlation);
+        println!("The {} teams that beat expectation by {} or more points were {:+.2} the next season; the {} that fell short by as much were {:+.2}.", carry.overperformers, carry.threshold, carry.overperformers_next, carry.underperformers, carry.underperformers_next);
+        println!();
+    }
+    for measure in [strength::OpponentStrength::Elo, strength::OpponentStrength::PointsPerGame] {
+        let early = strength::schedule_after(games, latest, 6, measure);
+        println!("After six matchweeks of the {} season, the toughest and easiest schedules by opponents' {}:", latest, measure.describe());
+        for point in early.iter().take(3).chain(early.iter().skip(early.len().saturating_sub(3))) {
+            println!("{}: {} points from {} games against opponents averaging {:.2} ({:.2} at home, {:.2} away)", point.team, point.points, point.games, point.opponent_strength, point.home_opponent_strength.unwrap_or(0.0), point.away_opponent_strength.unwrap_or(0.0));
+        }
+    }
+    println!();
+    if halftime::has_half_times(games) { // only sources with half-time scores, such as football-data.co.uk files, have these
+        println!("Half-time and full-time, by season:");
+        for season in &all_seasons {
+            let matrix = halftime::transitions(games, None, &[*season]);
+            if matrix.games() == 0 {
+                continue;
+            }
+            let comebacks = matrix.comeback_wins() + matrix.counts[0][2]; // away wins from losing at half-time are home sides losing a lead
+            println!("{}: {:.2}% of goals in the second half, home sides leading at half-time won {:.2}% of the time, {} comeback wins", season, halftime::second_half_share(games, None, &[*season]).unwrap_or(0.0), matrix.hold_rate(0).unwrap_or(0.0), comebacks);
+        }
+        println!();
+        halftime::transitions(games, None, &all_seasons).print();
+        println!();
+        println!("The five best teams at coming back from losing at half-time:");
+        for matrix in halftime::comeback_table(games, &all_teams, &all_seasons).iter().take(5) {
+            println!("{}: {} points from {} comeback wins and {} comeback draws, {} points dropped from winning positions", matrix.label, matrix.points_won_from_losing(), matrix.comeback_wins(), matrix.comeback_draws(), matrix.points_dropped_from_winning());
+        }
+        println!();
+    }
+    if matchstats::has_stats(games) { // only sources with the extended stats columns have bookings and attendances
+        let cards = matchstats::card_differential(games, &all_seasons);
+        if !cards.is_empty() {
+            println!("Bookings per game by season (a red counts as two yellows in the differential):");
+            for row in &cards {
+                println!("{}{}: home {:.2} yellows and {:.3} reds, away {:.2} yellows and {:.3} reds, away sides booked {:.3} more per game", row.season, if row.closed_doors { " (closed doors)" } else { "" }, row.home_yellows, row.home_reds, row.away_yellows, row.away_reds, row.differential());
+            }
+            println!();
+        }
+        let bands = matchstats::attendance_bands(games);
+        if !bands.is_empty() {
+            println!("Results by attendance:");
+            for band in &bands {
+                println!("{}: {} games, {:.2}% home wins, {:.2}% draws, {:.2}% away wins, {:.3} goals per game", band.label, band.games, band.home_win_rate, band.draw_rate, band.away_win_rate, band.goals_per_game);
+            }
+            println!();
+        }
+    }
+    if df.leagues().len() > 1 {
+        println!("Comparing the {} leagues loaded:", df.leagues().len());
+        for other in df.leagues() {
+            let league_games: Vec<&Game> = df.league_games(&other.name).iter().collect();
+            let Some(rates) = calendar::breakdown(other.name.clone(), &league_games) else {
+                continue;
+            };
+            println!("{}: {} games, {:.2}% home wins, {:.2}% draws, {:.2}% away wins, {:.3} goals per game", rates.label, rates.games, rates.home_win_rate, rates.draw_rate, rates.away_win_rate, rates.goals_per_game);
+        }
+        let every_game: Vec<&Game> = df.games().iter().collect();
+        if let Some(rates) = calendar::breakdown("All leagues".to_string(), &every_game) {
+            println!("{}: {} games, {:.2}% home wins, {:.2}% draws, {:.2}% away wins, {:.3} goals per game", rates.label, rates.games, rates.home_win_rate, rates.draw_rate, rates.away_win_rate, rates.goals_per_game);
+        }
+        println!();
+    }
+    let _ = user_choice(games, &all_seasons, &all_teams, &league.name);
 }
 
 #[test]
 fn test_result_rates() { // test whether all the result rates add up to 100
     let mut df = game::DataFrame::new();
-    let games = df.read_csv("pl_matches.csv").unwrap();
+    let games = df.read_csv("pl_matches.csv", League::premier_league()).unwrap();
     let season = 2023;
-    let mut total_rate = home_pct(&games, season.clone());
-    total_rate += draw_pct(&games, season.clone());
-    total_rate += away_pct(&games, season.clone());
+    let mut total_rate = home_pct(games, season.clone());
+    total_rate += draw_pct(games, season.clone());
+    total_rate += away_pct(games, season.clone());
     assert_eq!(total_rate, 100.0, "The total results percentage should be 100% for any given season!");
 }
 
 #[test]
 fn test_team_appearances() { // test the season appearances with a team that appeared in every season
     let mut df = game::DataFrame::new();
-    let games = df.read_csv("pl_matches.csv").unwrap();
+    let games = df.read_csv("pl_matches.csv", League::premier_league()).unwrap();
     let manutd = String::from("Manchester Utd");
     let seasons: Vec<usize> = (1993..=2023).collect();
-    let manutd_seasons = team_seasons(&games, &manutd, &seasons).1;
+    let manutd_seasons = team_seasons(games, &manutd, &seasons).1;
     assert_eq!(manutd_seasons, seasons.len(), "Manchester Utd has played in every season of the Premier League!");
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_matchweek_series() { // with a one-week window, every game should be counted exactly once across the series
+    let mut df = game::DataFrame::new();
+    let games = df.read_csv("pl_matches.csv", League::premier_league()).unwrap();
+    let series = advantage::matchweek_series(games, 1);
+    let counted: usize = series.iter().map(|p| p.games).sum();
+    assert_eq!(counted, games.len(), "Each game belongs to exactly one matchweek!");
+}
+
+#[cfg(test)]
+fn game(date: &str, home: &str, away: &str, home_goals: usize, away_goals: usize) -> Game {
+    // a hand-built Premier League game for tests, in the season that ends in the summer after its date.
+    use chrono::Datelike;
+    let date = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap();
+    Game {
+        season: date.year() as usize + usize::from(date.month() >= 8),
+        week: 1,
+        date,
+        home: home.to_string(),
+        away: away.to_string(),
+        home_goals,
+        away_goals,
+        result: (if home_goals > away_goals { "H" } else if home_goals == away_goals { "D" } else { "A" }).to_string(),
+        competition: "Premier League".to_string(),
+        ht_home_goals: None,
+        ht_away_goals: None,
+        stats: None,
+    }
+}
+
+#[test]
+fn test_home_edge() { // hand-built games: the edge is home minus away points per game, and only games after the restart count as closed doors
+    let games = vec![
+        game("2019-09-01", "Alpha", "Beta", 2, 0), // with crowds: Alpha win at home and lose away
+        game("2020-02-01", "Beta", "Alpha", 1, 0),
+        game("2020-06-20", "Alpha", "Beta", 1, 1), // behind closed doors: Alpha draw at home and win away
+        game("2020-07-01", "Beta", "Alpha", 0, 2),
+    ];
+    let alpha = "Alpha".to_string();
+    assert_eq!(advantage::home_away_ppg(&games, Some(&alpha), 2020), Some((2.0, 1.5)));
+    assert_eq!(advantage::home_edge(&games, Some(&alpha), 2020), Some(0.5));
+    assert_eq!(advantage::home_edge(&games, None, 2020), Some(0.75), "Home sides took 7 points from the 4 games and away sides 4!");
+    assert_eq!(advantage::home_edge(&games, Some(&alpha), 2019), None);
+    let teams: HashSet<String> = ["Alpha".to_string(), "Beta".to_string()].into_iter().collect();
+    let drops = advantage::edge_drop(&games, &teams, &[2020]);
+    assert_eq!(drops, vec![("Alpha".to_string(), 5.0), ("Beta".to_string(), 4.0)], "Alpha's edge went from +3 with crowds to -2 without, and Beta's from +3 to -1!");
+    let mut svg = String::new();
+    charts::home_edge_chart(&SVGBackend::with_string(&mut svg, (640, 480)).into_drawing_area(), &games, Some(&alpha), &[2020]).unwrap(); // a single season
+    assert!(svg.contains("Home edge"));
+}
+
+#[test]
+fn test_team_win_rate_counts_away_wins() { // a regression test: the original nested ifs only looked at away games inside the home branch, so away wins never counted
+    let games = vec![game("2000-01-01", "Alpha", "Beta", 0, 1), game("2000-01-08", "Beta", "Alpha", 0, 1), game("2000-01-15", "Gamma", "Alpha", 0, 2), game("2000-01-22", "Alpha", "Gamma", 1, 1)];
+    let alpha = "Alpha".to_string();
+    assert_eq!(team_wins(&games, &alpha, &[2000]), (2, 4), "Alpha lost and drew at home but won both away games!");
+    assert_eq!(team_win_rate(&games, &alpha, &[2000]), 50.0);
+    assert_eq!(team_win_rate(&games, &"Beta".to_string(), &[2000]), 50.0);
+}
+
+#[test]
+fn test_calendar_breakdowns() { // every game should fall on exactly one weekday and in one month, in season order, with rates adding up to 100
+    let mut df = game::DataFrame::new();
+    let games = df.read_csv("pl_matches.csv", League::premier_league()).unwrap();
+    let days = calendar::by_weekday(games);
+    assert_eq!(days[0].label, "Mon");
+    assert_eq!(days.iter().map(|d| d.games).sum::<usize>(), games.len());
+    let saturday = days.iter().find(|d| d.label == "Sat").unwrap();
+    assert!(days.iter().all(|d| d.games <= saturday.games), "Saturday is the traditional matchday!");
+    let months = calendar::by_month(games);
+    assert_eq!(months[0].label, "Aug");
+    assert_eq!(months.iter().map(|m| m.games).sum::<usize>(), games.len());
+    for group in days.iter().chain(&months) {
+        assert!((group.home_win_rate + group.draw_rate + group.away_win_rate - 100.0).abs() < 1e-9);
+    }
+    let (Some(festive), Some(rest)) = calendar::festive_split(games) else {
+        panic!("Every season has games in and out of the festive period!");
+    };
+    assert_eq!(festive.games + rest.games, games.len());
+    let autumn: Vec<Game> = games.iter().filter(|g| chrono::Datelike::month(&g.date) == 10).cloned().collect();
+    let (festive, rest) = calendar::festive_split(&autumn);
+    assert!(festive.is_none() && rest.is_some(), "October games are never festive!");
+    assert!(calendar::breakdown("Nothing".to_string(), &[]).is_none());
+    let opening: Vec<Game> = games.iter().filter(|g| g.season == 2023 && g.week == 1).cloned().collect();
+    assert!(calendar::congestion_bands(&opening).is_empty(), "No team has a previous game in the opening week!");
+    assert!(calendar::is_festive(chrono::NaiveDate::from_ymd_opt(2022, 12, 26).unwrap()) && !calendar::is_festive(chrono::NaiveDate::from_ymd_opt(2023, 1, 5).unwrap()));
+    let appearances: usize = calendar::congestion_bands(games).iter().map(|b| b.1).sum();
+    let rested: usize = calendar::rest_days(games).iter().map(|(h, a)| h.is_some() as usize + a.is_some() as usize).sum();
+    assert_eq!(appearances, rested, "Every appearance after a team's first of the season should be in one rest band!");
+}
+
+#[test]
+fn test_biggest_wins() { // the biggest winning margin in PL history is 9 goals, and a team's biggest win must be a game they won
+    let mut df = game::DataFrame::new();
+    let games = df.read_csv("pl_matches.csv", League::premier_league()).unwrap();
+    let seasons: Vec<usize> = (1993..=2023).collect();
+    let top = records::leaderboard(games, Ranking::BiggestWins, None, &seasons, 5);
+    assert_eq!(top[0].home_goals.abs_diff(top[0].away_goals), 9, "The biggest win in PL history was by 9 goals!");
+    let arsenal = String::from("Arsenal");
+    let best = greatest_interval(games, &arsenal, &seasons).unwrap();
+    assert!((best.home == arsenal && best.result == "H") || (best.away == arsenal && best.result == "A"));
+    assert!(greatest_interval(games, &arsenal, &[1900]).is_none(), "There is no biggest win in a season the team didn't play!");
+}
+
+#[test]
+fn test_season_summary() { // check the rebuilt table against a known season: Manchester City won in 2023, and four teams went down in 1995
+    let mut df = game::DataFrame::new();
+    let games = df.read_csv("pl_matches.csv", League::premier_league()).unwrap();
+    let seasons: Vec<usize> = (1993..=2023).collect();
+    let report = summary::season_summary(games, 2023, &seasons).unwrap();
+    assert_eq!(report.champion, "Manchester City");
+    assert_eq!(report.table.len(), 20);
+    assert_eq!(standings::relegated(games, 1995).len(), 4, "The league shrank from 22 to 20 teams after 1995!");
+    assert!(summary::season_summary(games, 1900, &seasons).is_none());
+    let points_of = |games: &[Game], team: &str| standings::league_table(games, 2010).into_iter().find(|r| r.team == team).unwrap().points;
+    let elsewhere: Vec<Game> = games.iter().filter(|g| g.season == 2010).map(|g| Game { competition: "Championship".to_string(), ..g.clone() }).collect();
+    assert_eq!(points_of(games, "Portsmouth") + 9, points_of(&elsewhere, "Portsmouth"), "Only the Premier League's Portsmouth was docked 9 points in 2010!");
+}
+
+#[test]
+fn test_html_report() { // the report should embed its charts and escape team names
+    let mut df = game::DataFrame::new();
+    let games = df.read_csv("pl_matches.csv", League::premier_league()).unwrap();
+    let seasons: Vec<usize> = (2021..=2023).collect();
+    let teams: HashSet<String> = games.iter().filter(|g| seasons.contains(&g.season)).map(|g| g.home.clone()).collect();
+    let html = report::html_report(games, &teams, &seasons, "Premier League").unwrap();
+    assert!(html.contains("<svg"), "The charts should be inline SVG!");
+    assert!(html.contains("Nott&#39;ham Forest") && !html.contains("Nott'ham Forest"));
+    assert!(report::html_report(games, &teams, &[], "Premier League").is_err(), "There's no report without seasons!");
+    let one_season = report::html_report(games, &teams, &[2023], "Premier League").unwrap();
+    assert!(one_season.contains("<svg") && !one_season.contains("NaN"), "A single season should still chart!");
+}
+
+#[test]
+fn test_server_routes() { // the JSON routes should decode team names and report unknown paths as 404s
+    let mut df = game::DataFrame::new();
+    let games = df.read_csv("pl_matches.csv", League::premier_league()).unwrap();
+    let seasons: Vec<usize> = (1993..=2023).collect();
+    let teams: HashSet<String> = games.iter().map(|g| g.home.clone()).collect();
+    let (status, body) = server::route(games, &teams, &seasons, "/seasons/2023/table");
+    assert_eq!(status, 200);
+    assert_eq!(body[0]["team"], "Manchester City");
+    let (status, body) = server::route(games, &teams, &seasons, "/teams/Nott%27ham%20Forest/summary");
+    assert_eq!(status, 200);
+    assert_eq!(body["team"], "Nott'ham Forest");
+    assert_eq!(server::route(games, &teams, &seasons, "/h2h/Arsenal/Nowhere FC").0, 404);
+    assert_eq!(server::route(games, &teams, &seasons, "/rates?group=season").1.as_array().unwrap().len(), seasons.len());
+    let (status, body) = server::route(games, &teams, &seasons, "/teams/Nott'ham+Forest/summary");
+    assert_eq!((status, body["error"].as_str()), (404, Some("No team called Nott'ham+Forest")), "A '+' in the path is not a space!");
+    assert_eq!(server::route(games, &teams, &seasons, "/rates?group=by+month").1["error"], "Unknown group 'by month'. Use season, matchweek, weekday or month.");
+    assert_eq!(server::route(games, &teams, &seasons, "/teams/%+1/summary").1["error"], "No team called %+1", "'+1' is not two hex digits!");
+}
+
+#[test]
+fn test_cache_round_trip() { // games loaded from the cache should match the csv, and editing the csv should make the cache stale
+    let dir = std::env::temp_dir().join(format!("pl_cache_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("pl_matches.csv");
+    let path = path.to_str().unwrap();
+    std::fs::copy("pl_matches.csv", path).unwrap();
+    let mut csv_df = game::DataFrame::new();
+    let from_csv = csv_df.read_cached(path, League::premier_league()).unwrap(); // no cache yet, so this reads the csv and writes one
+    assert!(std::path::Path::new(&game::cache_path(path)).exists());
+    let mut cache_df = game::DataFrame::new();
+    let from_cache = cache_df.read_cached(path, League::premier_league()).unwrap();
+    assert_eq!(from_csv.len(), from_cache.len());
+    for (a, b) in from_csv.iter().zip(from_cache.iter()) {
+        assert!(a.date == b.date && a.home == b.home && a.away == b.away && a.home_goals == b.home_goals && a.result == b.result);
+    }
+    let mut csv = std::fs::read_to_string(path).unwrap();
+    csv.push_str("2023,38,2023-05-28,Arsenal,9,0,Chelsea,H\n");
+    std::fs::write(path, csv).unwrap();
+    let mut changed_df = game::DataFrame::new();
+    assert_eq!(changed_df.read_cached(path, League::premier_league()).unwrap().len(), from_csv.len() + 1, "A changed csv should not be served from the old cache!");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_stream_csv() { // streaming the csv should give the same games, in the same order, as loading it all at once
+    let mut df = game::DataFrame::new();
+    let games = df.read_csv("pl_matches.csv", League::premier_league()).unwrap();
+    let stream = game::stream_csv("pl_matches.csv", &League::premier_league()).unwrap();
+    assert_eq!(stream.headers[0], "Season_End_Year");
+    let mut count = 0;
+    for (streamed, loaded) in stream.zip(games.iter()) {
+        let streamed = streamed.unwrap();
+        assert!(streamed.date == loaded.date && streamed.home == loaded.home && streamed.away_goals == loaded.away_goals);
+        count += 1;
+    }
+    assert_eq!(count, games.len());
+}
+
+#[test]
+fn test_multiple_leagues() { // games from a second league should be tagged with it and kept apart from the Premier League's
+    let path = std::env::temp_dir().join(format!("championship_test_{}.csv", std::process::id()));
+    let path = path.to_str().unwrap();
+    std::fs::write(path, "Season_End_Year,Wk,Date,Home,HomeGoals,AwayGoals,Away,FTR\n2023,1,2022-07-29,Huddersfield,0,1,Burnley,A\n2023,1,2022-07-30,Luton Town,0,0,Birmingham City,D\n").unwrap();
+    let mut df = DataFrame::new();
+    let pl_count = df.read_csv("pl_matches.csv", League::premier_league()).unwrap().len();
+    let championship = df.read_csv(path, League::from_name("Championship")).unwrap();
+    assert_eq!(championship.len(), 2);
+    assert!(championship.iter().all(|g| g.competition == "Championship"));
+    assert_eq!(df.league_games("Premier League").len(), pl_count);
+    assert_eq!(df.games().len(), pl_count + 2);
+    assert_eq!(df.leagues()[1].code, "E1");
+    assert_eq!(game::seasons_in(df.league_games("Championship")), vec![2023]);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn test_football_data_import() { // a football-data.co.uk file should read into Games with our team names and a derived season and matchweek
+    let path = std::env::temp_dir().join(format!("football_data_test_{}.csv", std::process::id()));
+    let path = path.to_str().unwrap();
+    std::fs::write(path, "\u{feff}Div,Date,HomeTeam,AwayTeam,FTHG,FTAG,FTR,HTHG,HTAG,HTR\nE0,26/07/2020,Man United,Leicester,2,0,H,0,0,D\nE0,12/09/2020,Fulham,Arsenal,0,3,A,0,1,A\nE0,19/09/2020,Arsenal,West Ham,2,1,H,1,1,D\nE1,12/09/2020,Luton,Barnsley,0,1,A,0,1,A\n,,,,,,,,,\n").unwrap();
+    let mut df = DataFrame::new();
+    let games = df.read_csv(path, League::premier_league()).unwrap();
+    assert_eq!(games.len(), 3, "The Championship game and the blank row should be skipped!");
+    assert_eq!((games[0].season, games[0].home.as_str(), games[0].away.as_str()), (2020, "Manchester Utd", "Leicester City"), "A late July game belongs to the season it finished!");
+    assert_eq!((games[1].season, games[1].week), (2021, 1));
+    assert_eq!((games[2].season, games[2].week), (2021, 2));
+    std::fs::remove_file(path).unwrap();
+
+    let mut pl = DataFrame::new();
+    let mut season = pl.read_csv("pl_matches.csv", League::premier_league()).unwrap().iter().filter(|g| g.season == 2019).cloned().collect::<Vec<Game>>();
+    let official: Vec<usize> = season.iter().map(|g| g.week).collect();
+    import::derive_weeks(&mut season, &vec![false; official.len()]);
+    let matching = season.iter().zip(&official).filter(|(g, w)| g.week == **w).count();
+    assert!(matching as f64 / official.len() as f64 > 0.9, "The derived matchweeks should mostly agree with the official ones!");
+}
+
+#[test]
+fn test_half_time_transitions() { // half-time scores should be read when the source has them and feed the comeback counts
+    let path = std::env::temp_dir().join(format!("half_time_test_{}.csv", std::process::id()));
+    let path = path.to_str().unwrap();
+    std::fs::write(path, "Div,Date,HomeTeam,AwayTeam,FTHG,FTAG,FTR,HTHG,HTAG,HTR\nE0,12/08/2023,Arsenal,Nott'm Forest,2,1,H,0,1,A\nE0,19/08/2023,Crystal Palace,Arsenal,0,1,A,0,0,D\nE0,26/08/2023,Arsenal,Fulham,2,2,D,1,0,H\nE0,02/09/2023,Fulham,Man United,1,3,A,1,1,D\n").unwrap();
+    let mut df = DataFrame::new();
+    let games = df.read_csv(path, League::premier_league()).unwrap();
+    assert!(halftime::has_half_times(games));
+    let arsenal = halftime::transitions(games, Some(&"Arsenal".to_string()), &[2024]);
+    assert_eq!(arsenal.games(), 3);
+    assert_eq!((arsenal.comeback_wins(), arsenal.points_won_from_losing(), arsenal.points_dropped_from_winning()), (1, 3, 2));
+    let home_sides = halftime::transitions(games, None, &[2024]);
+    assert_eq!(home_sides.counts[1][2], 2, "Two home sides were level at half-time and lost!");
+    assert_eq!(halftime::second_half_share(games, None, &[2024]), Some(8.0 / 12.0 * 100.0));
+    std::fs::remove_file(path).unwrap();
+
+    let mut pl = DataFrame::new();
+    let games = pl.read_csv("pl_matches.csv", League::premier_league()).unwrap();
+    assert!(!halftime::has_half_times(games), "pl_matches.csv has no half-time scores, so none should be made up!");
+}
+
+#[test]
+fn test_match_stats() { // the extended stats should be read from the extra columns and survive being written back out in our layout
+    let dir = std::env::temp_dir();
+    let source = dir.join(format!("match_stats_test_{}.csv", std::process::id()));
+    let written = dir.join(format!("match_stats_written_{}.csv", std::process::id()));
+    let (source, written) = (source.to_str().unwrap(), written.to_str().unwrap());
+    std::fs::write(source, "Div,Date,HomeTeam,AwayTeam,FTHG,FTAG,FTR,Attendance,Referee,HS,AS,HY,AY,HR,AR\nE0,19/08/2000,Charlton,Man City,4,0,H,\"20,043\",R Styles,17,8,1,2,0,0\nE0,19/08/2000,Chelsea,West Ham,4,2,H,34914,G Poll,17,12,1,2,0,1\nE0,01/02/2020,Chelsea,Man City,0,0,D,40100,M Dean,11,10,2,2,0,0\nE0,20/06/2020,Chelsea,Man City,2,1,H,0,A Taylor,9,18,3,1,0,1\n").unwrap();
+    let mut df = DataFrame::new();
+    let games = df.read_csv(source, League::premier_league()).unwrap();
+    let stats = games[0].stats.clone().unwrap();
+    assert_eq!((stats.attendance, stats.referee.as_deref(), stats.home_shots, stats.home_corners), (Some(20043), Some("R Styles"), Some(17), None));
+    let cards = matchstats::card_differential(games, &[2001, 2020]);
+    assert_eq!(cards.iter().map(|c| (c.season, c.closed_doors, c.games)).collect::<Vec<_>>(), vec![(2001, false, 2), (2020, false, 1), (2020, true, 1)], "2019-20 was split by the suspension!");
+    assert_eq!(cards[0].differential(), 2.0, "Away sides got 4 yellows and a red to the home sides' 2 yellows over two games!");
+    let bands = matchstats::attendance_bands(games);
+    assert_eq!(bands.iter().map(|b| (b.label.as_str(), b.games)).collect::<Vec<_>>(), vec![("Behind closed doors", 1), ("20000 to 29999", 1), ("30000 to 39999", 1), ("40000 to 49999", 1)]);
+
+    game::write_csv(written, games).unwrap();
+    let mut reread = DataFrame::new();
+    let round_trip = reread.read_csv(written, League::premier_league()).unwrap();
+    assert_eq!(round_trip.iter().map(|g| g.stats.clone()).collect::<Vec<_>>(), games.iter().map(|g| g.stats.clone()).collect::<Vec<_>>());
+    std::fs::remove_file(source).unwrap();
+    std::fs::remove_file(written).unwrap();
+}
+
+#[test]
+fn test_sqlite_round_trip() { // games saved to the database should load back the same, and the metrics should give the same answers on them
+    let path = std::env::temp_dir().join(format!("pl_test_{}.db", std::process::id()));
+    let path = path.to_str().unwrap();
+    let mut csv_df = DataFrame::new();
+    csv_df.read_csv("pl_matches.csv", League::premier_league()).unwrap();
+    database::write_database(&csv_df, path).unwrap();
+    assert_eq!(database::leagues(path).unwrap(), vec![League::premier_league()]);
+    let mut db_df = DataFrame::new();
+    let from_db = db_df.read_sqlite(path, League::premier_league()).unwrap();
+    let from_csv = csv_df.league_games("Premier League");
+    assert_eq!(from_db.len(), from_csv.len());
+    assert!(from_db.iter().zip(from_csv).all(|(a, b)| (a.date, &a.home, &a.away, a.home_goals, a.away_goals, &a.result) == (b.date, &b.home, &b.away, b.home_goals, b.away_goals, &b.result)));
+    assert_eq!(home_pct(from_db, 2021), home_pct(from_csv, 2021));
+    assert_eq!(standings::league_table(from_db, 2016)[0].team, "Leicester City");
+    database::write_database(&csv_df, path).unwrap(); // writing over our own database replaces its tables
+    assert_eq!(DataFrame::new().read_sqlite(path, League::premier_league()).unwrap().len(), from_csv.len());
+    std::fs::remove_file(path).unwrap();
+
+    let other = std::env::temp_dir().join(format!("pl_test_other_{}.db", std::process::id()));
+    let other = other.to_str().unwrap();
+    std::fs::write(other, "not a database").unwrap();
+    assert!(database::write_database(&csv_df, other).is_err());
+    assert_eq!(std::fs::read_to_string(other).unwrap(), "not a database", "A file that isn't ours should be left alone!");
+    std::fs::remove_file(other).unwrap();
+    rusqlite::Connection::open(other).unwrap().execute_batch("CREATE TABLE notes (text TEXT); CREATE TABLE games (id INTEGER)").unwrap();
+    assert!(database::write_database(&csv_df, other).is_err(), "Another program's database shouldn't lose its games table!");
+    std::fs::remove_file(other).unwrap();
+}
+
+#[test]
+fn test_elo_ratings() { // the season-end ratings should cover every team each season and put the champions near the top
+    let mut df = DataFrame::new();
+    let games = df.read_csv("pl_matches.csv", League::premier_league()).unwrap();
+    let ratings = ratings::season_ratings(games);
+    let latest: Vec<&ratings::SeasonRating> = ratings.iter().filter(|r| r.season == 2023).collect();
+    assert_eq!(latest.len(), 20);
+    let best = latest.iter().max_by(|a, b| a.rating.total_cmp(&b.rating)).unwrap();
+    assert_eq!(best.team, "Manchester City");
+    let average = ratings.iter().map(|r| r.rating).sum::<f64>() / ratings.len() as f64;
+    assert!((average - ratings::INITIAL).abs() < 25.0, "Ratings are only moved between teams, so the average should stay near where it started!");
+}
+
+#[test]
+fn test_parquet_and_arrow_round_trip() { // every column, including the optional ones, should come back from both file formats unchanged
+    let dir = std::env::temp_dir();
+    let source = dir.join(format!("columnar_test_{}.csv", std::process::id()));
+    let parquet = dir.join(format!("columnar_test_{}.parquet", std::process::id()));
+    let arrow = dir.join(format!("columnar_test_{}.arrow", std::process::id()));
+    let (source, parquet, arrow) = (source.to_str().unwrap(), parquet.to_str().unwrap(), arrow.to_str().unwrap());
+    std::fs::write(source, "Div,Date,HomeTeam,AwayTeam,FTHG,FTAG,FTR,HTHG,HTAG,Attendance,Referee,HY,AY\nE0,19/08/2000,Charlton,Man City,4,0,H,2,0,20043,R Styles,1,2\nE0,20/06/2020,Chelsea,Man City,2,1,H,,,0,,3,1\n").unwrap();
+    let mut df = DataFrame::new();
+    df.read_csv("pl_matches.csv", League::premier_league()).unwrap();
+    df.read_csv(source, League::from_name("Imported")).unwrap();
+    let expected = serde_json::to_value(df.games()).unwrap();
+    columnar::write_parquet(df.games(), parquet).unwrap();
+    columnar::write_ipc(df.games(), arrow).unwrap();
+    let mut from_parquet = DataFrame::new();
+    assert_eq!(serde_json::to_value(from_parquet.read_parquet(parquet).unwrap()).unwrap(), expected);
+    assert_eq!(from_parquet.leagues().len(), 2);
+    let mut from_arrow = DataFrame::new();
+    assert_eq!(serde_json::to_value(from_arrow.read_ipc(arrow).unwrap()).unwrap(), expected);
+    for path in [source, parquet, arrow] {
+        std::fs::remove_file(path).unwrap();
+    }
+    let mut far_future = df.games()[0].clone();
+    far_future.season = 70000;
+    assert!(columnar::to_record_batch(&[far_future]).is_err(), "A season that doesn't fit in a u16 shouldn't be written as a smaller one!");
+    let batch = columnar::to_record_batch(&df.games()[..2]).unwrap();
+    let with_column = |name: &str, values: arrow::array::ArrayRef| {
+        let (index, _) = batch.schema().column_with_name(name).unwrap();
+        let mut columns = batch.columns().to_vec();
+        columns[index] = values;
+        let mut fields: Vec<arrow::datatypes::Field> = batch.schema().fields().iter().map(|f| f.as_ref().clone()).collect();
+        fields[index] = arrow::datatypes::Field::new(name, columns[index].data_type().clone(), true);
+        arrow::array::RecordBatch::try_new(std::sync::Arc::new(arrow::datatypes::Schema::new(fields)), columns).unwrap()
+    };
+    let wide_week = with_column("week", std::sync::Arc::new(arrow::array::Int64Array::from(vec![1, 300])));
+    assert!(columnar::from_record_batch(&wide_week).is_err(), "A week of 300 doesn't fit in a u8 and shouldn't be read as missing!");
+    let missing_season = with_column("season", std::sync::Arc::new(arrow::array::UInt16Array::from(vec![Some(2000), None])));
+    assert!(columnar::from_record_batch(&missing_season).is_err(), "Every game needs a season!");
+}
+
+#[test]
+fn test_fixture_list() { // a generated season should have everyone play everyone home and away, once a week, with few home/away breaks
+    let teams: Vec<String> = (1..=20).map(|i| format!("Team {}", i)).collect();
+    let generated = fixtures::double_round_robin(&teams);
+    assert_eq!(generated.len(), 380);
+    for week in 1..=38 {
+        let playing: HashSet<&String> = generated.iter().filter(|f| f.week == week).flat_map(|f| [&f.home, &f.away]).collect();
+        assert_eq!(playing.len(), 20, "Every team should play exactly once in week {}!", week);
+    }
+    let pairs: HashSet<(&String, &String)> = generated.iter().map(|f| (&f.home, &f.away)).collect();
+    assert_eq!(pairs.len(), 380, "Every team should host every other team exactly once!");
+    let stats = fixtures::schedule_stats("Generated", &generated);
+    assert_eq!(stats.breaks, 3 * 20 - 6, "A mirrored double round-robin can't have fewer than 3n - 6 breaks!");
+    assert_eq!(stats.shortest_meeting_gap, 19);
+
+    let odd = fixtures::double_round_robin(&teams[..5]);
+    assert_eq!((odd.len(), odd.iter().map(|f| f.week).max()), (20, Some(10)), "With five teams one sits out each week!");
+
+    let mut df = DataFrame::new();
+    let games = df.read_csv("pl_matches.csv", League::premier_league()).unwrap();
+    let real = fixtures::schedule_stats("2023", &fixtures::season_fixtures(games, 2023));
+    assert_eq!((real.teams, real.weeks, real.fixtures), (20, 38, 380));
+}
+
+#[test]
+fn test_strength_of_schedule() { // the schedule numbers should add up, and the adjusted rates should move towards teams that faced strong opponents
+    let mut df = DataFrame::new();
+    let games = df.read_csv("pl_matches.csv", League::premier_league()).unwrap();
+    let early = strength::schedule_after(games, 2023, 6, strength::OpponentStrength::PointsPerGame);
+    assert_eq!(early.len(), 20);
+    assert!(early.windows(2).all(|w| w[0].opponent_strength >= w[1].opponent_strength), "The toughest schedule should come first!");
+    let city = strength::strength_of_schedule(games, 2023, strength::OpponentStrength::Elo).into_iter().filter(|p| p.team == "Manchester City").collect::<Vec<_>>();
+    assert_eq!((city.len(), city.last().unwrap().points), (38, 89));
+    let arsenal = "Arsenal".to_string();
+    let win_rate = team_win_rate(games, &arsenal, &[2023]);
+    assert!((win_rate - 26.0 / 38.0 * 100.0).abs() < 1e-9, "Arsenal won 26 of their 38 games in 2022-23, home and away!");
+    let adjusted_win_rate = strength::adjusted_win_rate(games, &arsenal, &[2023]).unwrap();
+    assert!((adjusted_win_rate - win_rate).abs() < 5.0, "Over a full season everyone plays everyone, so the adjustment should be small!");
+    assert!((ratings::win_probability(0.0, 0.25) - 0.375).abs() < 1e-12, "Equal sides that draw a quarter of the time should split the rest!");
+    assert!(ratings::win_probability(100.0, 0.25) > 0.375 && ratings::win_probability(-100.0, 0.25) < 0.375);
+    let (score_rate, adjusted) = strength::adjusted_score_rate(games, &arsenal, &[2023]).unwrap();
+    assert!((score_rate - (26.0 + 6.0 / 2.0) / 38.0 * 100.0).abs() < 1e-9, "Arsenal won 26 and drew 6, so they scored 29 of a possible 38!");
+    assert!((adjusted - score_rate).abs() < 5.0, "Over a full season everyone plays everyone, so the adjustment should be small!");
+}
+
+#[test]
+fn test_expected_points() { // expected points should track actual points closely, and the luck left over shouldn't carry into the next season
+    let mut df = DataFrame::new();
+    let games = df.read_csv("pl_matches.csv", League::premier_league()).unwrap();
+    let seasons = game::seasons_in(games);
+    let exponent = luck::fit_exponent(games, &seasons);
+    assert!((1.0..2.0).contains(&exponent), "Football's Pythagorean exponent is usually found between 1 and 2, not {}!", exponent);
+    for model in [luck::Model::Pythagorean(exponent), luck::Model::Poisson] {
+        let table = luck::luck_table(games, 2023, model);
+        assert_eq!(table.len(), 20);
+        assert!(table.windows(2).all(|w| w[0].difference() >= w[1].difference()));
+        let spread = table.iter().map(|r| r.difference().abs()).sum::<f64>() / 20.0;
+        assert!(spread < 6.0, "Teams usually finish within a few points of their expectation!");
+        let carry = luck::regress_to_mean(games, &seasons, model, 5.0);
+        assert!(carry.correlation.abs() < 0.3, "Points above expectation should be mostly luck, so hardly correlated year to year!");
+    }
+}
+
+#[test]
+fn test_backtest() { // predictions should be proper probabilities, and the rating-based predictors should beat the flat historical rates
+    let mut df = DataFrame::new();
+    let games = df.read_csv("pl_matches.csv", League::premier_league()).unwrap();
+    let mut scores = Vec::new();
+    for mut predictor in predict::predictors() {
+        let predictions = predict::walk_forward(games, predictor.as_mut());
+        assert_eq!(predictions.len(), games.len());
+        assert!(predictions.iter().all(|p| (p.probabilities.home + p.probabilities.draw + p.probabilities.away - 1.0).abs() < 1e-9), "{} should give probabilities that sum to one!", predictor.name());
+        scores.push(predict::score(predictor.name(), &predictions.iter().collect::<Vec<_>>()));
+    }
+    let home_share = games.iter().filter(|g| g.result == "H").count() as f64 / games.len() as f64 * 100.0;
+    assert!((scores[0].accuracy - home_share).abs() < 1e-9, "Always picking the home side is right exactly as often as the home side wins!");
+    assert!(scores[2].log_loss < scores[1].log_loss, "Elo should beat the league-wide rates!");
+    assert!(scores[3].log_loss < scores[1].log_loss, "The Poisson model should beat the league-wide rates!");
+    assert!(scores[1].log_loss < 3f64.ln() + 0.01, "The historical rates should be about as good as a third each, or better!");
+}
+
+#[test]
+fn test_calibration() { // always predicting a home win is badly calibrated, while Elo's probabilities should be close to the truth
+    let mut df = DataFrame::new();
+    let games = df.read_csv("pl_matches.csv", League::premier_league()).unwrap();
+    let home_share = games.iter().filter(|g| g.result == "H").count() as f64 / games.len() as f64;
+    let always = calibration::calibration(&predict::walk_forward(games, &mut predict::HomeAlways), 10);
+    assert_eq!(always[0].bins.len(), 1, "Every prediction is 100%, so there's only one bin!");
+    assert!((always[0].expected_calibration_error - (1.0 - home_share)).abs() < 1e-9);
+    let elo = calibration::calibration(&predict::walk_forward(games, &mut predict::EloPredictor::new()), 10);
+    for curve in &elo {
+        assert_eq!(curve.bins.iter().map(|b| b.predictions).sum::<usize>(), games.len());
+        assert!(curve.expected_calibration_error < 0.05, "Elo's {} probabilities should be within five points on average!", curve.outcome);
+    }
+}
+
+#[test]
+fn test_confidence_intervals() { // the intervals should match textbook values and sit around the plain rates the rest of the analysis uses
+    let half = stats::wilson(5, 10);
+    assert!((half.lower - 0.2366).abs() < 1e-4 && (half.upper - 0.7634).abs() < 1e-4, "5 out of 10 has a Wilson interval of 23.7% to 76.3%!");
+    let none = stats::wilson(0, 20);
+    assert!(none.lower.abs() < 1e-12 && none.upper > 0.1, "The Wilson interval for no successes shouldn't collapse to zero width!");
+    let mut df = DataFrame::new();
+    let games = df.read_csv("pl_matches.csv", League::premier_league()).unwrap();
+    let home = home_estimate(games, 2021);
+    assert!((home.value - home_pct(games, 2021)).abs() < 1e-9);
+    assert!(home.lower < home.value && home.value < home.upper);
+    let arsenal = "Arsenal".to_string();
+    assert!((team_win_estimate(games, &arsenal, &[2023]).value - team_win_rate(games, &arsenal, &[2023])).abs() < 1e-9);
+    let goals = goal_avg_estimate(games, 2021);
+    assert!((goals.value - goal_avg(games, 2021)).abs() < 1e-9);
+    assert!(goals.lower < goals.value && goals.value < goals.upper && goals.upper - goals.lower < 0.5);
+    assert_eq!(goals, goal_avg_estimate(games, 2021), "The bootstrap is seeded, so it should give the same interval every time!");
+}
+
+#[test]
+fn test_changepoints() { // a stretch at a different level should be found at its edges, a flat series should have no breaks, and 2021 should stand out
+    let mut rng = stats::Rng::new(7);
+    let noise = |rng: &mut stats::Rng| (rng.below(1000) as f64 / 1000.0 - 0.5) * 4.0;
+    let series: Vec<changepoint::Point> = (0..50).map(|i| changepoint::Point { season: 1990 + i, week: None, games: 380, value: if (20..30).contains(&i) { 10.0 } else { 0.0 } + noise(&mut rng) }).collect();
+    let breaks = changepoint::changepoints(&series, 3, 999);
+    assert_eq!(breaks.iter().map(|b| b.season).collect::<Vec<usize>>(), vec![2010, 2020]);
+    assert!((breaks[0].after_mean - 10.0).abs() < 1.0 && (breaks[1].after_mean).abs() < 1.0);
+    let flat: Vec<changepoint::Point> = (0..50).map(|i| changepoint::Point { season: 1990 + i, week: None, games: 380, value: noise(&mut rng) }).collect();
+    assert!(changepoint::changepoints(&flat, 3, 999).is_empty(), "Noise alone shouldn't give any significant breaks!");
+
+    let mut df = DataFrame::new();
+    let games = df.read_csv("pl_matches.csv", League::premier_league()).unwrap();
+    let seasons = game::seasons_in(games);
+    let real = changepoint::changepoints(&changepoint::season_series(games, &seasons), 1, 999);
+    assert!(real.iter().any(|b| b.season == 2021 && b.after_mean < b.before_mean), "The closed-doors season should be a break!");
+}
+
+#[test]
+fn test_regression() { // the models should converge to sensible signs, and the controlled closed-doors effect should cut the home edge
+    assert!((stats::normal_cdf(1.959964) - 0.975).abs() < 1e-6);
+    let inverse = stats::invert(&[vec![4.0, 7.0], vec![2.0, 6.0]]).unwrap();
+    assert!((inverse[0][0] - 0.6).abs() < 1e-12 && (inverse[0][1] + 0.7).abs() < 1e-12 && (inverse[1][0] + 0.2).abs() < 1e-12 && (inverse[1][1] - 0.4).abs() < 1e-12);
+    assert!(stats::invert(&[vec![1.0, 2.0], vec![2.0, 4.0]]).is_none(), "A singular matrix has no inverse!");
+
+    let mut df = DataFrame::new();
+    let games = df.read_csv("pl_matches.csv", League::premier_league()).unwrap();
+    let ordinal = regression::ordinal_logit(games).unwrap();
+    assert!(ordinal.coefficient("rating difference").unwrap().z() > 10.0, "The better-rated side should be far more likely to win!");
+    assert!(ordinal.coefficient("away win | draw cut").unwrap().estimate < ordinal.coefficient("draw | home win cut").unwrap().estimate);
+    assert_eq!(games.iter().filter(|g| advantage::behind_closed_doors(g)).count(), 92 + 380, "Only the 92 games after the 2019-20 restart and all of 2020-21 were played without fans!");
+    assert_eq!(advantage::first_closed_doors_week(games), Some((2020, 30)), "Weeks 28 and 29 were mostly played before the suspension!");
+    let pre_covid: Vec<Game> = games.iter().filter(|g| g.season < 2020).cloned().collect();
+    assert!(regression::ordinal_logit(&pre_covid).is_err(), "Without closed-doors games that covariate can't be estimated!");
+    let closed = ordinal.coefficient("closed doors").unwrap();
+    assert!(closed.estimate < 0.0 && closed.p_value() < 0.01, "Closed doors should move results towards the away side!");
+    let multinomial = regression::multinomial_logit(games).unwrap();
+    assert!(multinomial.log_likelihood >= ordinal.log_likelihood, "The multinomial model has more freedom, so it can't fit worse!");
+    let goals = regression::poisson_goals(games).unwrap();
+    let home = goals.coefficient("at home").unwrap().estimate.exp();
+    assert!((1.2..1.5).contains(&home), "Home sides usually score about a third more goals!");
+    assert!(goals.coefficient("at home × closed doors").unwrap().estimate < 0.0);
+}
+
+#[test]
+fn test_team_comparison() { // the comparison rows should agree with the single-team functions, and head-to-head records should balance
+    let mut df = DataFrame::new();
+    let games = df.read_csv("pl_matches.csv", League::premier_league()).unwrap();
+    let teams = vec!["Arsenal".to_string(), "Manchester City".to_string(), "Leicester City".to_string()];
+    let seasons: Vec<usize> = (2014..=2023).collect();
+    let rows = compare::compare(games, &teams, &seasons);
+    for row in &rows {
+        let team = &row.team;
+        assert!((row.win_rate - team_win_rate(games, team, &seasons)).abs() < 1e-9);
+        assert_eq!(row.seasons, team_seasons(games, team, &seasons).1);
+    }
+    assert_eq!((rows[1].best_finish, rows[2].best_finish), (Some(1), Some(1)), "City and Leicester both won the league in that time!");
+    let ppg = compare::season_points_per_game(games, &teams[1], &[2023]);
+    assert!((ppg[0].1 - 89.0 / 38.0).abs() < 1e-9);
+    let records = compare::head_to_heads(games, &teams, &seasons);
+    assert_eq!(records.len(), 3);
+    let reverse = compare::head_to_heads(games, &[teams[1].clone(), teams[0].clone()], &seasons);
+    assert_eq!((records[0].won, records[0].drawn, records[0].lost), (reverse[0].lost, reverse[0].drawn, reverse[0].won));
+    assert_eq!(records[0].played, head_to_head(games, &teams[0], &teams[1], &seasons).len());
+}
+
+#[test]
+fn test_bump_chart() { // final positions should skip seasons outside the league, and the last matchweek's position should be the final one
+    let mut df = DataFrame::new();
+    let games = df.read_csv("pl_matches.csv", League::premier_league()).unwrap();
+    let leicester = compare::finishes(games, &"Leicester City".to_string(), &(2005..=2016).collect::<Vec<usize>>());
+    assert_eq!(leicester.last(), Some(&(2016, 1)), "Leicester won the league in 2016!");
+    assert!(leicester.iter().all(|(season, _)| *season >= 2015), "Leicester were outside the league before 2015!");
+    let city = standings::positions_by_week(games, 2023, &"Manchester City".to_string());
+    assert_eq!(city.len(), 38);
+    assert_eq!(city[city.len() - 1], (38, 1));
+    let mut svg = String::new();
+    {
+        let drawing_area = SVGBackend::with_string(&mut svg, (640, 360)).into_drawing_area();
+        charts::bump_chart(&drawing_area, "Test", "Matchweek", &[("Manchester City".to_string(), city)], 20).unwrap();
+        assert!(charts::bump_chart(&drawing_area, "Test", "Matchweek", &[], 20).is_err(), "There's nothing to draw without positions!");
+    }
+    assert!(svg.contains("Manchester City"));
+}
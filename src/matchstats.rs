@@ -0,0 +1,81 @@
+use serde::Serialize;
+use crate::Game;
+use crate::advantage::behind_closed_doors;
+use crate::calendar::{breakdown, Breakdown};
+
+// a module for the analyses that need the extended stats: bookings, to look for refereeing bias with and without crowds,
+// and attendances, to look at crowd size directly. games without the stats are left out.
+
+pub const ATTENDANCE_BANDS: [(usize, usize); 5] = [(1, 20000), (20000, 30000), (30000, 40000), (40000, 50000), (50000, usize::MAX)]; // (from, below)
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CardRow {
+    // the bookings per game for home and away sides in one season.
+    pub season: usize,
+    pub games: usize,
+    pub home_yellows: f64,
+    pub away_yellows: f64,
+    pub home_reds: f64,
+    pub away_reds: f64,
+    pub closed_doors: bool,
+}
+
+impl CardRow {
+    pub fn differential(&self) -> f64 {
+        // how many more cards away sides got per game than home sides, counting a red as two yellows.
+        (self.away_yellows + 2.0 * self.away_reds) - (self.home_yellows + 2.0 * self.home_reds)
+    }
+}
+
+pub fn has_stats(games: &[Game]) -> bool {
+    games.iter().any(|g| g.stats.is_some())
+}
+
+pub fn card_differential(games: &[Game], seasons: &[usize]) -> Vec<CardRow> {
+    // the home and away bookings for each season that has them. only games with all four card counts are used.
+    // a season that was partly played behind closed doors, like 2019-20, gets one row for the games with crowds and one for those without.
+    let mut rows = Vec::new();
+    for (season, closed_doors) in seasons.iter().flat_map(|s| [(s, false), (s, true)]) {
+        let mut counted = 0;
+        let mut totals = [0; 4];
+        for game in games.iter().filter(|g| g.season == *season && behind_closed_doors(g) == closed_doors) {
+            let Some(stats) = &game.stats else {
+                continue;
+            };
+            if let (Some(hy), Some(ay), Some(hr), Some(ar)) = (stats.home_yellows, stats.away_yellows, stats.home_reds, stats.away_reds) {
+                counted += 1;
+                for (total, cards) in totals.iter_mut().zip([hy, ay, hr, ar]) {
+                    *total += cards;
+                }
+            }
+        }
+        if counted == 0 {
+            continue;
+        }
+        let per_game = |total: usize| total as f64 / counted as f64;
+        rows.push(CardRow {
+            season: *season,
+            games: counted,
+            home_yellows: per_game(totals[0]),
+            away_yellows: per_game(totals[1]),
+            home_reds: per_game(totals[2]),
+            away_reds: per_game(totals[3]),
+            closed_doors,
+        });
+    }
+    rows
+}
+
+pub fn attendance_bands(games: &[Game]) -> Vec<Breakdown> {
+    // the result rates for games grouped by the size of the crowd, with the games played behind closed doors as a band of their own.
+    let attended: Vec<(&Game, usize)> = games.iter().filter_map(|g| Some((g, g.stats.as_ref()?.attendance?))).collect();
+    let mut bands = Vec::new();
+    let empty: Vec<&Game> = attended.iter().filter(|(_, crowd)| *crowd == 0).map(|(g, _)| *g).collect();
+    bands.extend(breakdown("Behind closed doors".to_string(), &empty));
+    for (from, below) in ATTENDANCE_BANDS {
+        let band: Vec<&Game> = attended.iter().filter(|(_, crowd)| *crowd >= from && *crowd < below).map(|(g, _)| *g).collect();
+        let label = if below == usize::MAX { format!("{} or more", from) } else { format!("{} to {}", from, below - 1) };
+        bands.extend(breakdown(label, &band));
+    }
+    bands
+}
@@ -0,0 +1,305 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use serde::Serialize;
+use crate::Game;
+use crate::ratings::Elo;
+use crate::stats::{poisson, MAX_GOALS};
+use crate::wins::{away_pct, draw_pct, home_pct};
+
+// a module for predicting results before they happen and scoring those predictions.
+// every predictor gives home/draw/away probabilities for a game from what it has learned so far, and the backtest
+// walks forward through the games, asking for each day's predictions before showing the predictors that day's results.
+
+const PROBABILITY_FLOOR: f64 = 1e-15; // probabilities are clipped to this before taking logs, so a certain prediction that's wrong scores badly but finitely
+const FORM_GAMES: usize = 38; // how many of a team's most recent games the Poisson predictor rates it on
+const PRIOR_GAMES: f64 = 5.0; // how many league-average games a team's record is padded with, so a few games can't give it extreme ratings
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Probabilities {
+    pub home: f64,
+    pub draw: f64,
+    pub away: f64,
+}
+
+impl Probabilities {
+    pub fn new(home: f64, draw: f64, away: f64) -> Self {
+        // scales the three so they sum to one.
+        let total = home + draw + away;
+        Probabilities { home: home / total, draw: draw / total, away: away / total }
+    }
+
+    pub fn of(&self, result: &str) -> f64 {
+        match result {
+            "H" => self.home,
+            "D" => self.draw,
+            _ => self.away,
+        }
+    }
+
+    pub fn most_likely(&self) -> &'static str {
+        // ties go to the home side, then the draw.
+        if self.home >= self.draw && self.home >= self.away {
+            "H"
+        } else if self.draw >= self.away {
+            "D"
+        } else {
+            "A"
+        }
+    }
+}
+
+pub trait Predictor {
+    fn name(&self) -> String;
+
+    // a prediction from the games learned so far. only the teams, date and season may be looked at, never the score.
+    fn predict(&self, game: &Game) -> Probabilities;
+
+    // takes in a game's result, once every game on its date has been predicted.
+    fn learn(&mut self, game: &Game);
+
+    // called before the first game of each season with the teams taking part, which are known from the fixture list in advance.
+    fn start_season(&mut self, _season: usize, _teams: &HashSet<&String>) {}
+}
+
+pub struct HomeAlways;
+
+impl Predictor for HomeAlways {
+    fn name(&self) -> String {
+        "Home win every time".to_string()
+    }
+
+    fn predict(&self, _game: &Game) -> Probabilities {
+        Probabilities::new(1.0, 0.0, 0.0)
+    }
+
+    fn learn(&mut self, _game: &Game) {}
+}
+
+pub struct HistoricalRates {
+    // the average home, draw and away rates of the completed seasons, worked out with the rates in wins.rs.
+    seen: Vec<Game>,
+    rates: Probabilities,
+}
+
+impl HistoricalRates {
+    pub fn new() -> Self {
+        HistoricalRates { seen: Vec::new(), rates: Probabilities::new(1.0, 1.0, 1.0) } // no history to go on before the first season ends
+    }
+}
+
+impl Predictor for HistoricalRates {
+    fn name(&self) -> String {
+        "Historical result rates".to_string()
+    }
+
+    fn predict(&self, _game: &Game) -> Probabilities {
+        self.rates
+    }
+
+    fn learn(&mut self, game: &Game) {
+        self.seen.push(game.clone());
+    }
+
+    fn start_season(&mut self, _season: usize, _teams: &HashSet<&String>) {
+        let seasons = crate::game::seasons_in(&self.seen);
+        if seasons.is_empty() {
+            return;
+        }
+        let average = |rate: fn(&[Game], usize) -> f64| seasons.iter().map(|s| rate(&self.seen, *s)).sum::<f64>() / seasons.len() as f64;
+        self.rates = Probabilities::new(average(home_pct), average(draw_pct), average(away_pct));
+    }
+}
+
+pub struct EloPredictor {
+    // Elo's expected score gives the home win probability plus half the draw probability; the draw probability is the
+    // draw rate seen so far, and the rest is split so the expected score still comes out right.
+    elo: Elo,
+    previous: HashSet<String>,
+    games: usize,
+    draws: usize,
+}
+
+impl EloPredictor {
+    pub fn new() -> Self {
+        EloPredictor { elo: Elo::new(), previous: HashSet::new(), games: 0, draws: 0 }
+    }
+}
+
+impl Predictor for EloPredictor {
+    fn name(&self) -> String {
+        "Elo ratings".to_string()
+    }
+
+    fn predict(&self, game: &Game) -> Probabilities {
+        let expected = self.elo.expected_home(&game.home, &game.away);
+        let draw = (self.draws as f64 + 1.0) / (self.games as f64 + 3.0);
+        let home = (expected - draw / 2.0).max(0.01);
+        let away = (1.0 - expected - draw / 2.0).max(0.01);
+        Probabilities::new(home, draw, away)
+    }
+
+    fn learn(&mut self, game: &Game) {
+        self.elo.update(game);
+        self.games += 1;
+        if game.result == "D" {
+            self.draws += 1;
+        }
+    }
+
+    fn start_season(&mut self, _season: usize, teams: &HashSet<&String>) {
+        let previous: HashSet<&String> = self.previous.iter().collect();
+        self.elo.start_season(&previous, teams);
+        self.previous = teams.iter().map(|t| (*t).clone()).collect();
+    }
+}
+
+pub struct PoissonPredictor {
+    // each side's goals are a Poisson draw whose mean is the league's average for its venue, scaled up by its attack
+    // and by the opponent's defence. attack and defence are goals scored and conceded relative to the league average
+    // over the team's recent games.
+    form: HashMap<String, VecDeque<(f64, f64)>>, // each team's recent (scored, conceded), relative to the league average at the venue
+    home_goals: usize,
+    away_goals: usize,
+    games: usize,
+}
+
+impl PoissonPredictor {
+    pub fn new() -> Self {
+        PoissonPredictor { form: HashMap::new(), home_goals: 0, away_goals: 0, games: 0 }
+    }
+
+    fn averages(&self) -> (f64, f64) {
+        // the league's home and away goals per game so far, starting from typical values.
+        let home = (self.home_goals as f64 + 1.5 * PRIOR_GAMES) / (self.games as f64 + PRIOR_GAMES);
+        let away = (self.away_goals as f64 + 1.1 * PRIOR_GAMES) / (self.games as f64 + PRIOR_GAMES);
+        (home, away)
+    }
+
+    fn strength(&self, team: &str) -> (f64, f64) {
+        // the team's (attack, defence), padded towards the league average of 1.
+        let recent = self.form.get(team);
+        let count = recent.map(|r| r.len()).unwrap_or(0) as f64;
+        let (scored, conceded) = recent.map(|r| r.iter().fold((0.0, 0.0), |acc, g| (acc.0 + g.0, acc.1 + g.1))).unwrap_or((0.0, 0.0));
+        ((scored + PRIOR_GAMES) / (count + PRIOR_GAMES), (conceded + PRIOR_GAMES) / (count + PRIOR_GAMES))
+    }
+
+    fn record(&mut self, team: &str, scored: f64, conceded: f64) {
+        let recent = self.form.entry(team.to_string()).or_default();
+        recent.push_back((scored, conceded));
+        if recent.len() > FORM_GAMES {
+            recent.pop_front();
+        }
+    }
+}
+
+impl Predictor for PoissonPredictor {
+    fn name(&self) -> String {
+        "Poisson goals".to_string()
+    }
+
+    fn predict(&self, game: &Game) -> Probabilities {
+        let (home_average, away_average) = self.averages();
+        let (home_attack, home_defence) = self.strength(&game.home);
+        let (away_attack, away_defence) = self.strength(&game.away);
+        let home_rate = home_average * home_attack * away_defence;
+        let away_rate = away_average * away_attack * home_defence;
+        let (mut home, mut draw, mut away) = (0.0, 0.0, 0.0);
+        for x in 0..=MAX_GOALS {
+            for y in 0..=MAX_GOALS {
+                let p = poisson(x, home_rate) * poisson(y, away_rate);
+                if x > y {
+                    home += p;
+                } else if x == y {
+                    draw += p;
+                } else {
+                    away += p;
+                }
+            }
+        }
+        Probabilities::new(home, draw, away)
+    }
+
+    fn learn(&mut self, game: &Game) {
+        let (home_average, away_average) = self.averages();
+        self.record(&game.home, game.home_goals as f64 / home_average, game.away_goals as f64 / away_average);
+        self.record(&game.away, game.away_goals as f64 / away_average, game.home_goals as f64 / home_average);
+        self.home_goals += game.home_goals;
+        self.away_goals += game.away_goals;
+        self.games += 1;
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Prediction {
+    pub season: usize,
+    pub result: String,
+    pub probabilities: Probabilities,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Score {
+    // how good a set of predictions was. lower log-loss and Brier scores are better; always giving a third to each result
+    // scores a log-loss of ln 3 (about 1.099) and a Brier score of 2/3.
+    pub label: String,
+    pub games: usize,
+    pub log_loss: f64,
+    pub brier: f64,
+    pub accuracy: f64,
+}
+
+pub fn walk_forward(games: &[Game], predictor: &mut dyn Predictor) -> Vec<Prediction> {
+    // predicts every game using only the games played on earlier dates, in the order they were played.
+    let mut order: Vec<&Game> = games.iter().collect();
+    order.sort_by_key(|g| (g.season, g.date));
+    let mut predictions = Vec::with_capacity(games.len());
+    for season_games in order.chunk_by(|a, b| a.season == b.season) {
+        let teams: HashSet<&String> = season_games.iter().flat_map(|g| [&g.home, &g.away]).collect();
+        predictor.start_season(season_games[0].season, &teams);
+        for day in season_games.chunk_by(|a, b| a.date == b.date) {
+            for game in day {
+                predictions.push(Prediction { season: game.season, result: game.result.clone(), probabilities: predictor.predict(game) });
+            }
+            for game in day {
+                predictor.learn(game);
+            }
+        }
+    }
+    predictions
+}
+
+pub fn score(label: String, predictions: &[&Prediction]) -> Score {
+    // the average log-loss and Brier score, and the percentage of results that were the most likely one.
+    let mut log_loss = 0.0;
+    let mut brier = 0.0;
+    let mut correct = 0;
+    for prediction in predictions {
+        let p = prediction.probabilities;
+        log_loss -= p.of(&prediction.result).max(PROBABILITY_FLOOR).ln();
+        for (result, probability) in [("H", p.home), ("D", p.draw), ("A", p.away)] {
+            let happened = if prediction.result == result { 1.0 } else { 0.0 };
+            brier += (probability - happened).powi(2);
+        }
+        if p.most_likely() == prediction.result {
+            correct += 1;
+        }
+    }
+    let n = predictions.len() as f64;
+    Score { label, games: predictions.len(), log_loss: log_loss / n, brier: brier / n, accuracy: correct as f64 / n * 100.0 }
+}
+
+pub fn score_by_season(predictions: &[Prediction]) -> Vec<Score> {
+    // one score per season, in order.
+    let seasons: Vec<usize> = {
+        let mut seasons: Vec<usize> = predictions.iter().map(|p| p.season).collect();
+        seasons.dedup();
+        seasons
+    };
+    seasons
+        .into_iter()
+        .map(|season| score(season.to_string(), &predictions.iter().filter(|p| p.season == season).collect::<Vec<_>>()))
+        .collect()
+}
+
+pub fn predictors() -> Vec<Box<dyn Predictor>> {
+    // every predictor, simplest first.
+    vec![Box::new(HomeAlways), Box::new(HistoricalRates::new()), Box::new(EloPredictor::new()), Box::new(PoissonPredictor::new())]
+}
@@ -0,0 +1,116 @@
+use std::collections::{HashMap, HashSet};
+use serde::Serialize;
+use crate::Game;
+
+// a module for Elo ratings: every team carries a rating that moves after each game by how surprising the result was.
+// teams new to the league in a season start at the average rating of the teams that left it, so promoted sides come in below the established ones.
+
+pub const INITIAL: f64 = 1500.0; // every team's rating in the first season of the data
+pub const K: f64 = 20.0; // how far one result moves a rating
+pub const HOME_ADVANTAGE: f64 = 60.0; // rating points added to the home side when working out the expected result
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SeasonRating {
+    // a team's rating at the end of a season.
+    pub season: usize,
+    pub team: String,
+    pub rating: f64,
+}
+
+pub fn expected_score(difference: f64) -> f64 {
+    // the expected score of a side rated `difference` points above its opponent, home advantage included.
+    1.0 / (1.0 + 10f64.powf(-difference / 400.0))
+}
+
+pub fn win_probability(difference: f64, draw_rate: f64) -> f64 {
+    // the chance that a side rated `difference` points above its opponent wins outright, under Davidson's extension of the
+    // model to draws: draws are likeliest between equal sides, who draw `draw_rate` of the time, and get rarer as the gap grows.
+    let ratio = 10f64.powf(difference / 400.0);
+    let tie = 2.0 * draw_rate / (1.0 - draw_rate); // Davidson's draw parameter, chosen so equal sides draw at draw_rate
+    ratio / (ratio + 1.0 + tie * ratio.sqrt())
+}
+
+pub struct Elo {
+    ratings: HashMap<String, f64>,
+}
+
+impl Elo {
+    pub fn new() -> Self {
+        Elo { ratings: HashMap::new() }
+    }
+
+    pub fn rating(&self, team: &String) -> f64 {
+        *self.ratings.get(team).unwrap_or(&INITIAL)
+    }
+
+    pub fn expected_home(&self, home: &String, away: &String) -> f64 {
+        // the home side's expected score (a win counting 1 and a draw 0.5) against the away side.
+        expected_score(self.rating(home) + HOME_ADVANTAGE - self.rating(away))
+    }
+
+    pub fn update(&mut self, game: &Game) {
+        // moves both ratings by the gap between the result and the expected one. the points one side gains the other loses.
+        let expected = self.expected_home(&game.home, &game.away);
+        let actual = match game.result.as_str() {
+            "H" => 1.0,
+            "D" => 0.5,
+            _ => 0.0,
+        };
+        let change = K * (actual - expected);
+        let home = self.rating(&game.home);
+        let away = self.rating(&game.away);
+        self.ratings.insert(game.home.clone(), home + change);
+        self.ratings.insert(game.away.clone(), away - change);
+    }
+
+    pub fn start_season(&mut self, previous: &HashSet<&String>, current: &HashSet<&String>) {
+        // gives the teams that weren't in the league last season the average end rating of the teams that left it.
+        let left: Vec<f64> = previous.iter().filter(|t| !current.contains(*t)).map(|t| self.rating(t)).collect();
+        if left.is_empty() {
+            return;
+        }
+        let average = left.iter().sum::<f64>() / left.len() as f64;
+        for team in current.iter().filter(|t| !previous.contains(*t)) {
+            self.ratings.insert((*team).clone(), average);
+        }
+    }
+}
+
+fn replay<'a>(games: &'a [Game], mut before_game: impl FnMut(usize, &Elo), mut end_of_season: impl FnMut(usize, &HashSet<&'a String>, &Elo)) {
+    // runs through the games in the order they were played, which the ratings have to be built in,
+    // calling before_game with each game's index before its result is counted and end_of_season with each season's teams after its last game.
+    let mut order: Vec<usize> = (0..games.len()).collect();
+    order.sort_by_key(|i| (games[*i].season, games[*i].date));
+    let mut elo = Elo::new();
+    let mut previous: HashSet<&String> = HashSet::new();
+    for season_order in order.chunk_by(|a, b| games[*a].season == games[*b].season) {
+        let current: HashSet<&String> = season_order.iter().flat_map(|i| [&games[*i].home, &games[*i].away]).collect();
+        elo.start_season(&previous, &current);
+        for i in season_order {
+            before_game(*i, &elo);
+            elo.update(&games[*i]);
+        }
+        end_of_season(games[season_order[0]].season, &current, &elo);
+        previous = current;
+    }
+}
+
+pub fn pre_game_ratings(games: &[Game]) -> Vec<(f64, f64)> {
+    // the (home, away) ratings going into every game, in the same order as games.
+    let mut ratings = vec![(INITIAL, INITIAL); games.len()];
+    replay(games, |i, elo| ratings[i] = (elo.rating(&games[i].home), elo.rating(&games[i].away)), |_, _, _| ());
+    ratings
+}
+
+pub fn season_ratings(games: &[Game]) -> Vec<SeasonRating> {
+    // every team's rating at the end of every season.
+    let mut ratings = Vec::new();
+    replay(games, |_, _| (), |season, teams, elo| {
+        let mut teams: Vec<&&String> = teams.iter().collect();
+        teams.sort();
+        for team in teams {
+            ratings.push(SeasonRating { season, team: (*team).clone(), rating: elo.rating(team) });
+        }
+    });
+    ratings
+}
@@ -0,0 +1,59 @@
+use crate::Game;
+
+// a module for ranking individual games: the biggest wins, heaviest defeats and highest-scoring games.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Ranking {
+    BiggestWins,
+    HeaviestDefeats,
+    HighestScoring,
+    HighestScoringDraws,
+}
+
+impl Ranking {
+    pub fn describe(&self) -> &'static str {
+        // a short name for the ranking to use in printed headings.
+        match self {
+            Ranking::BiggestWins => "biggest wins",
+            Ranking::HeaviestDefeats => "heaviest defeats",
+            Ranking::HighestScoring => "highest-scoring games",
+            Ranking::HighestScoringDraws => "highest-scoring draws",
+        }
+    }
+}
+
+fn qualifies(game: &Game, ranking: Ranking, team: Option<&String>) -> bool {
+    // whether a game belongs in the ranking. without a team every game counts, so wins and defeats are the same list.
+    let team_won = team.is_none_or(|t| (*t == game.home && game.result == "H") || (*t == game.away && game.result == "A"));
+    let team_lost = team.is_none_or(|t| (*t == game.home && game.result == "A") || (*t == game.away && game.result == "H"));
+    let team_played = team.is_none_or(|t| *t == game.home || *t == game.away);
+    match ranking {
+        Ranking::BiggestWins => game.result != "D" && team_won,
+        Ranking::HeaviestDefeats => game.result != "D" && team_lost,
+        Ranking::HighestScoring => team_played,
+        Ranking::HighestScoringDraws => game.result == "D" && team_played,
+    }
+}
+
+fn score(game: &Game, ranking: Ranking) -> usize {
+    // the value a game is ranked by: the winning margin for wins and defeats, total goals otherwise.
+    match ranking {
+        Ranking::BiggestWins | Ranking::HeaviestDefeats => game.home_goals.abs_diff(game.away_goals),
+        Ranking::HighestScoring | Ranking::HighestScoringDraws => game.home_goals + game.away_goals,
+    }
+}
+
+pub fn leaderboard(games: &[Game], ranking: Ranking, team: Option<&String>, seasons: &[usize], n: usize) -> Vec<Game> {
+    // returns up to n games from the given seasons, ranked from the top. ties go to the game with more goals, then the earlier one.
+    // with a team only that team's games are considered; with None the whole league is.
+    let mut ranked: Vec<&Game> = games
+        .iter()
+        .filter(|g| seasons.contains(&g.season) && qualifies(g, ranking, team))
+        .collect();
+    ranked.sort_by(|a, b| {
+        score(b, ranking).cmp(&score(a, ranking))
+            .then((b.home_goals + b.away_goals).cmp(&(a.home_goals + a.away_goals)))
+            .then(a.date.cmp(&b.date))
+    });
+    ranked.into_iter().take(n).cloned().collect()
+}
@@ -0,0 +1,283 @@
+use std::error::Error;
+use chrono::Datelike;
+use serde::Serialize;
+use crate::Game;
+use crate::advantage::behind_closed_doors;
+use crate::calendar::rest_days;
+use crate::game::MyError;
+use crate::ratings::pre_game_ratings;
+use crate::stats::{invert, normal_cdf, Z_95};
+
+// a module of regression models for results and goals, so the effect of playing behind closed doors can be estimated
+// while holding the teams' strength, the era, the time of year and the rest each side had constant.
+// every model is fitted by maximum likelihood with Newton's method, and the standard errors come from the inverse of the
+// information matrix (the negative Hessian of the log-likelihood) at the fit.
+
+const MAX_ITERATIONS: usize = 50;
+const TOLERANCE: f64 = 1e-8; // the fit stops once no coefficient moves by more than this in a step
+const MAX_REST: f64 = 14.0; // rest days are capped here, so an international break counts the same as a fortnight
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Coefficient {
+    pub name: String,
+    pub estimate: f64,
+    pub standard_error: f64,
+}
+
+impl Coefficient {
+    pub fn z(&self) -> f64 {
+        self.estimate / self.standard_error
+    }
+
+    pub fn p_value(&self) -> f64 {
+        // two-sided, against a coefficient of zero.
+        2.0 * (1.0 - normal_cdf(self.z().abs()))
+    }
+
+    pub fn describe(&self) -> String {
+        format!("{}: {:.4} (SE {:.4}, 95% CI {:.4} to {:.4}, p = {:.4})", self.name, self.estimate, self.standard_error, self.estimate - Z_95 * self.standard_error, self.estimate + Z_95 * self.standard_error, self.p_value())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Fit {
+    pub model: String,
+    pub observations: usize,
+    pub log_likelihood: f64,
+    pub iterations: usize,
+    pub coefficients: Vec<Coefficient>,
+}
+
+impl Fit {
+    pub fn coefficient(&self, name: &str) -> Option<&Coefficient> {
+        self.coefficients.iter().find(|c| c.name == name)
+    }
+
+    pub fn print(&self) {
+        println!("{} ({} observations, log-likelihood {:.2}, {} iterations):", self.model, self.observations, self.log_likelihood, self.iterations);
+        for coefficient in &self.coefficients {
+            println!("  {}", coefficient.describe());
+        }
+    }
+}
+
+pub struct Design {
+    // one row of covariates per game, in the same order as the games, without an intercept.
+    pub names: Vec<String>,
+    pub rows: Vec<Vec<f64>>,
+}
+
+fn month_name(month: u32) -> String {
+    chrono::Month::try_from(month as u8).map(|m| m.name().to_string()).unwrap_or_default()
+}
+
+pub fn design(games: &[Game]) -> Design {
+    // the covariates, from the home side's point of view:
+    // the Elo rating difference before the game (home minus away, per 100 points), whether it was behind closed doors,
+    // the season (in decades since the first), the rest difference in days (home minus away, 0 when either side's first game
+    // of the season) and a dummy for each month, with August as the baseline.
+    let ratings = pre_game_ratings(games);
+    let rests = rest_days(games);
+    let first = games.iter().map(|g| g.season).min().unwrap_or(0);
+    let mut months: Vec<u32> = games.iter().map(|g| g.date.month()).filter(|m| *m != 8).collect();
+    months.sort_by_key(|m| (*m + 4) % 12); // in season order, September first
+    months.dedup();
+    let mut names: Vec<String> = ["rating difference", "closed doors", "season", "rest difference"].iter().map(|n| n.to_string()).collect();
+    names.extend(months.iter().map(|m| month_name(*m)));
+    let rows = games
+        .iter()
+        .zip(ratings.iter().zip(rests))
+        .map(|(game, ((home_rating, away_rating), rest))| {
+            let rest_difference = match rest {
+                (Some(home), Some(away)) => (home as f64).min(MAX_REST) - (away as f64).min(MAX_REST),
+                _ => 0.0,
+            };
+            let mut row = vec![(home_rating - away_rating) / 100.0, if behind_closed_doors(game) { 1.0 } else { 0.0 }, (game.season - first) as f64 / 10.0, rest_difference];
+            row.extend(months.iter().map(|m| if game.date.month() == *m { 1.0 } else { 0.0 }));
+            row
+        })
+        .collect();
+    Design { names, rows }
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn add_outer(matrix: &mut [Vec<f64>], a: &[f64], b: &[f64], weight: f64) {
+    // matrix += weight × a bᵀ
+    for (row, x) in matrix.iter_mut().zip(a) {
+        for (cell, y) in row.iter_mut().zip(b) {
+            *cell += weight * x * y;
+        }
+    }
+}
+
+fn newton<F>(model: &str, names: Vec<String>, observations: usize, start: Vec<f64>, evaluate: F) -> Result<Fit, Box<dyn Error>>
+where F: Fn(&[f64]) -> (f64, Vec<f64>, Vec<Vec<f64>>) {
+    // maximises a log-likelihood given its value, gradient and Hessian at any parameters. a step that lowers the likelihood
+    // is halved until it doesn't, which keeps the fit stable from a poor start.
+    let mut params = start;
+    let (mut log_likelihood, mut gradient, mut hessian) = evaluate(&params);
+    for iteration in 1..=MAX_ITERATIONS {
+        let information: Vec<Vec<f64>> = hessian.iter().map(|row| row.iter().map(|h| -h).collect()).collect();
+        let inverse = invert(&information).ok_or_else(|| MyError(format!("The {} model can't be fitted: its covariates are collinear", model)))?;
+        let mut step: Vec<f64> = inverse.iter().map(|row| dot(row, &gradient)).collect();
+        loop {
+            let candidate: Vec<f64> = params.iter().zip(&step).map(|(p, s)| p + s).collect();
+            let next = evaluate(&candidate);
+            if next.0 >= log_likelihood - 1e-9 || step.iter().all(|s| s.abs() < TOLERANCE) {
+                params = candidate;
+                (log_likelihood, gradient, hessian) = next;
+                break;
+            }
+            step.iter_mut().for_each(|s| *s /= 2.0);
+        }
+        if step.iter().all(|s| s.abs() < TOLERANCE) {
+            let information: Vec<Vec<f64>> = hessian.iter().map(|row| row.iter().map(|h| -h).collect()).collect();
+            let covariance = invert(&information).ok_or_else(|| MyError(format!("The {} model's information matrix is singular", model)))?;
+            let coefficients = names
+                .into_iter()
+                .enumerate()
+                .map(|(i, name)| Coefficient { name, estimate: params[i], standard_error: covariance[i][i].sqrt() })
+                .collect();
+            return Ok(Fit { model: model.to_string(), observations, log_likelihood, iterations: iteration, coefficients });
+        }
+    }
+    Err(MyError(format!("The {} model didn't converge in {} iterations", model, MAX_ITERATIONS)).into())
+}
+
+fn logistic(x: f64) -> f64 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+pub fn ordinal_logit(games: &[Game]) -> Result<Fit, Box<dyn Error>> {
+    // a proportional-odds model of the result, ordered away win < draw < home win: P(result ≤ j) = σ(cut_j - xβ).
+    // a positive coefficient moves results towards the home side. there's no intercept; the two cutpoints take its place.
+    let design = design(games);
+    let outcomes: Vec<usize> = games.iter().map(|g| match g.result.as_str() { "A" => 0, "D" => 1, _ => 2 }).collect();
+    let p = design.names.len();
+    let evaluate = |params: &[f64]| {
+        let (beta, cuts) = params.split_at(p);
+        let mut log_likelihood = 0.0;
+        let mut gradient = vec![0.0; p + 2];
+        let mut hessian = vec![vec![0.0; p + 2]; p + 2];
+        for (x, outcome) in design.rows.iter().zip(&outcomes) {
+            let eta = dot(beta, x);
+            let (f1, f2) = (logistic(cuts[0] - eta), logistic(cuts[1] - eta));
+            let (d1, d2) = (f1 * (1.0 - f1), f2 * (1.0 - f2)); // the logistic density at each cut
+            // the log-likelihood's first and second derivatives with respect to a1 = cut_1 - xβ and a2 = cut_2 - xβ
+            let (probability, g, h) = match outcome {
+                0 => (f1, [1.0 - f1, 0.0], [[-d1, 0.0], [0.0, 0.0]]),
+                1 => {
+                    let middle = (f2 - f1).max(1e-300);
+                    let (s1, s2) = (d1 * (1.0 - 2.0 * f1), d2 * (1.0 - 2.0 * f2)); // the density's slope at each cut
+                    (middle, [-d1 / middle, d2 / middle], [[-s1 / middle - (d1 / middle).powi(2), d1 * d2 / middle.powi(2)], [d1 * d2 / middle.powi(2), s2 / middle - (d2 / middle).powi(2)]])
+                },
+                _ => (1.0 - f2, [0.0, -f2], [[0.0, 0.0], [0.0, -d2]]),
+            };
+            log_likelihood += probability.max(1e-300).ln();
+            // each a_j moves with its cut and against xβ, so its gradient with respect to the parameters is (-x, e_j)
+            let directions: Vec<Vec<f64>> = (0..2).map(|j| x.iter().map(|v| -v).chain((0..2).map(|k| if k == j { 1.0 } else { 0.0 })).collect()).collect();
+            for j in 0..2 {
+                for (total, d) in gradient.iter_mut().zip(&directions[j]) {
+                    *total += g[j] * d;
+                }
+                for k in 0..2 {
+                    if h[j][k] != 0.0 {
+                        add_outer(&mut hessian, &directions[j], &directions[k], h[j][k]);
+                    }
+                }
+            }
+        }
+        (log_likelihood, gradient, hessian)
+    };
+    let mut names = design.names.clone();
+    names.extend(["away win | draw cut".to_string(), "draw | home win cut".to_string()]);
+    let mut start = vec![0.0; p];
+    start.extend([-1.0, 0.2]); // roughly the league's usual 30% away wins and 25% draws
+    newton("Ordinal logistic regression of the result", names, games.len(), start, evaluate)
+}
+
+pub fn multinomial_logit(games: &[Game]) -> Result<Fit, Box<dyn Error>> {
+    // the log-odds of a home win and of an away win, each against a draw, with their own intercept and coefficients.
+    // unlike the ordinal model, a covariate can make both decisive results more likely at once.
+    let design = design(games);
+    let p = design.names.len() + 1;
+    let rows: Vec<Vec<f64>> = design.rows.iter().map(|r| std::iter::once(1.0).chain(r.iter().copied()).collect()).collect();
+    let outcomes: Vec<Option<usize>> = games.iter().map(|g| match g.result.as_str() { "H" => Some(0), "A" => Some(1), _ => None }).collect();
+    let evaluate = |params: &[f64]| {
+        let mut log_likelihood = 0.0;
+        let mut gradient = vec![0.0; 2 * p];
+        let mut hessian = vec![vec![0.0; 2 * p]; 2 * p];
+        for (x, outcome) in rows.iter().zip(&outcomes) {
+            let scores = [dot(&params[..p], x), dot(&params[p..], x)];
+            let normaliser = 1.0 + scores[0].exp() + scores[1].exp();
+            let probabilities = [scores[0].exp() / normaliser, scores[1].exp() / normaliser];
+            log_likelihood -= normaliser.ln();
+            if let Some(k) = outcome {
+                log_likelihood += scores[*k];
+            }
+            for k in 0..2 {
+                let observed = if *outcome == Some(k) { 1.0 } else { 0.0 };
+                for (total, v) in gradient[k * p..(k + 1) * p].iter_mut().zip(x) {
+                    *total += (observed - probabilities[k]) * v;
+                }
+                for l in 0..2 {
+                    let weight = -probabilities[k] * (if k == l { 1.0 } else { 0.0 } - probabilities[l]);
+                    for (i, xi) in x.iter().enumerate() {
+                        for (j, xj) in x.iter().enumerate() {
+                            hessian[k * p + i][l * p + j] += weight * xi * xj;
+                        }
+                    }
+                }
+            }
+        }
+        (log_likelihood, gradient, hessian)
+    };
+    let mut names = Vec::new();
+    for side in ["home win", "away win"] {
+        names.push(format!("{}: intercept", side));
+        names.extend(design.names.iter().map(|n| format!("{}: {}", side, n)));
+    }
+    newton("Multinomial logistic regression of the result (against a draw)", names, games.len(), vec![0.0; 2 * p], evaluate)
+}
+
+pub fn poisson_goals(games: &[Game]) -> Result<Fit, Box<dyn Error>> {
+    // a log-linear model of the goals each side scores, with one observation per side per game.
+    // "at home × closed doors" is how much of the home side's scoring edge went missing without fans, as a log rate ratio.
+    // the other covariates are from the scoring side's point of view: its rating and rest minus its opponent's.
+    let design = design(games);
+    let mut names = vec!["intercept".to_string(), "at home".to_string(), "at home × closed doors".to_string()];
+    names.extend(design.names.iter().cloned());
+    let mut rows = Vec::with_capacity(2 * games.len());
+    let mut goals = Vec::with_capacity(2 * games.len());
+    for (game, x) in games.iter().zip(&design.rows) {
+        for at_home in [true, false] {
+            let sign = if at_home { 1.0 } else { -1.0 };
+            let closed = x[1];
+            let mut row = vec![1.0, if at_home { 1.0 } else { 0.0 }, if at_home { closed } else { 0.0 }, sign * x[0], closed, x[2], sign * x[3]];
+            row.extend(x[4..].iter().copied());
+            rows.push(row);
+            goals.push(if at_home { game.home_goals } else { game.away_goals } as f64);
+        }
+    }
+    let p = names.len();
+    let log_factorials: f64 = goals.iter().map(|y| (1..=*y as usize).map(|k| (k as f64).ln()).sum::<f64>()).sum(); // the part of the likelihood that doesn't depend on the parameters
+    let evaluate = |params: &[f64]| {
+        let mut log_likelihood = -log_factorials;
+        let mut gradient = vec![0.0; p];
+        let mut hessian = vec![vec![0.0; p]; p];
+        for (x, y) in rows.iter().zip(&goals) {
+            let eta = dot(params, x);
+            let mean = eta.exp();
+            log_likelihood += y * eta - mean;
+            for (total, v) in gradient.iter_mut().zip(x) {
+                *total += (y - mean) * v;
+            }
+            add_outer(&mut hessian, x, x, -mean);
+        }
+        (log_likelihood, gradient, hessian)
+    };
+    newton("Poisson regression of goals scored", names, rows.len(), vec![0.0; p], evaluate)
+}
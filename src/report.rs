@@ -0,0 +1,121 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt::Write as _;
+use plotters::prelude::*;
+use crate::Game;
+use crate::advantage::home_edge;
+use crate::charts;
+use crate::game::MyError;
+use crate::records::{leaderboard, Ranking};
+use crate::standings::{league_table, TableRow};
+use crate::wins::{away_pct, draw_pct, home_pct};
+
+// a module that writes every summary table and chart into one self-contained HTML file.
+// the charts are drawn with SVGBackend and pasted straight into the page, so the file can be opened or shared on its own.
+
+const STYLE: &str = "body { font-family: sans-serif; max-width: 960px; margin: 2em auto; color: #222; }
+table { border-collapse: collapse; margin: 1em 0; }
+th, td { padding: 0.2em 0.6em; text-align: right; border-bottom: 1px solid #ddd; }
+th:first-child, td:first-child, td.team { text-align: left; }
+.charts svg { margin: 0.5em 0; }
+nav a { margin-right: 0.6em; }";
+
+fn escape(text: &str) -> String {
+    // escapes the characters that would break the HTML, e.g. the apostrophe in Nott'ham Forest.
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;").replace('\'', "&#39;")
+}
+
+fn anchor(team: &str) -> String {
+    // a link target for a team's section.
+    team.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>().to_lowercase()
+}
+
+fn svg<F>(size: (u32, u32), draw: F) -> Result<String, Box<dyn Error>>
+where F: FnOnce(&DrawingArea<SVGBackend, plotters::coord::Shift>) -> Result<(), Box<dyn Error>> {
+    // renders a chart into an SVG string.
+    let mut buffer = String::new();
+    {
+        let drawing_area = SVGBackend::with_string(&mut buffer, size).into_drawing_area();
+        draw(&drawing_area)?;
+    }
+    Ok(buffer)
+}
+
+pub fn html_report(games: &[Game], teams: &HashSet<String>, seasons: &[usize], league: &str) -> Result<String, Box<dyn Error>> {
+    // builds the full report page for one league as a string.
+    let (Some(&first), Some(&last)) = (seasons.first(), seasons.last()) else {
+        return Err(Box::new(MyError("No seasons to report on".to_string())));
+    };
+    let mut tables: HashMap<usize, Vec<TableRow>> = HashMap::new();
+    for season in seasons {
+        tables.insert(*season, league_table(games, *season));
+    }
+    let mut team_names: Vec<&String> = teams.iter().collect();
+    team_names.sort();
+
+    let mut html = String::new();
+    writeln!(html, "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{} results {}-{}</title>\n<style>\n{}\n</style>\n</head>\n<body>", escape(league), first, last, STYLE)?;
+    writeln!(html, "<h1>{} results {}-{}</h1>", escape(league), first, last)?;
+    writeln!(html, "<p>Over {} seasons, a total of {} teams have competed in the {}, playing {} games.</p>", seasons.len(), teams.len(), escape(league), games.iter().filter(|g| seasons.contains(&g.season)).count())?;
+
+    writeln!(html, "<h2>Most successful teams</h2>")?;
+    writeln!(html, "<table>\n<tr><th>#</th><th>Team</th><th>Win percentage</th></tr>")?;
+    for (i, (team, pct)) in crate::top_percent(games, teams, 10, seasons).iter().enumerate() {
+        writeln!(html, "<tr><td>{}</td><td class=\"team\"><a href=\"#{}\">{}</a></td><td>{:.2}%</td></tr>", i + 1, anchor(team), escape(team), pct)?;
+    }
+    writeln!(html, "</table>")?;
+    writeln!(html, "<table>\n<tr><th>#</th><th>Team</th><th>Seasons</th></tr>")?;
+    for (i, (team, apps)) in crate::top_appearances(games, teams, seasons, 10).iter().enumerate() {
+        writeln!(html, "<tr><td>{}</td><td class=\"team\"><a href=\"#{}\">{}</a></td><td>{}</td></tr>", i + 1, anchor(team), escape(team), apps)?;
+    }
+    writeln!(html, "</table>")?;
+
+    writeln!(html, "<h2>Seasons</h2>\n<div class=\"charts\">")?;
+    html.push_str(&svg((640, 480), |area| charts::result_rates_chart(area, games, seasons))?);
+    html.push_str(&svg((640, 480), |area| charts::goal_averages_chart(area, games, seasons))?);
+    html.push_str(&svg((640, 480), |area| charts::home_edge_chart(area, games, None, seasons))?);
+    writeln!(html, "</div>")?;
+    writeln!(html, "<table>\n<tr><th>Season</th><th>Champion</th><th>Home wins</th><th>Draws</th><th>Away wins</th><th>Goals per game</th><th>Home edge (PPG)</th></tr>")?;
+    for season in seasons {
+        let champion = tables[season].first().map(|r| r.team.clone()).unwrap_or_default();
+        let edge = home_edge(games, None, *season).unwrap_or(0.0);
+        writeln!(html, "<tr><td>{}</td><td class=\"team\">{}</td><td>{:.2}%</td><td>{:.2}%</td><td>{:.2}%</td><td>{:.3}</td><td>{:.3}</td></tr>", season, escape(&champion), home_pct(games, *season), draw_pct(games, *season), away_pct(games, *season), crate::goal_avg(games, *season), edge)?;
+    }
+    writeln!(html, "</table>")?;
+
+    writeln!(html, "<h2>Teams</h2>\n<nav>")?;
+    for team in &team_names {
+        write!(html, "<a href=\"#{}\">{}</a> ", anchor(team), escape(team))?;
+    }
+    writeln!(html, "\n</nav>")?;
+    for team in &team_names {
+        let (played, count) = crate::team_seasons(games, team, seasons);
+        if count == 0 {
+            continue;
+        }
+        writeln!(html, "<h3 id=\"{}\">{}</h3>", anchor(team), escape(team))?;
+        writeln!(html, "<p>{} seasons in the league with a win percentage of {:.2}%.</p>", count, crate::team_win_rate(games, team, seasons))?;
+        if let Some(win) = leaderboard(games, Ranking::BiggestWins, Some(team), seasons, 1).pop() {
+            writeln!(html, "<p>Biggest win: {} {}-{} {} on {}.</p>", escape(&win.home), win.home_goals, win.away_goals, escape(&win.away), win.date)?;
+        }
+        writeln!(html, "<table>\n<tr><th>Season</th><th>Position</th><th>W</th><th>D</th><th>L</th><th>GF</th><th>GA</th><th>Pts</th><th>Home edge (PPG)</th></tr>")?;
+        for season in &played {
+            let table = &tables[season];
+            let position = table.iter().position(|r| r.team == **team).unwrap();
+            let row = &table[position];
+            let edge = home_edge(games, Some(team), *season).unwrap_or(0.0);
+            writeln!(html, "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{:.3}</td></tr>", season, position + 1, row.won, row.drawn, row.lost, row.goals_for, row.goals_against, row.points, edge)?;
+        }
+        writeln!(html, "</table>\n<div class=\"charts\">")?;
+        html.push_str(&svg((640, 360), |area| charts::home_edge_chart(area, games, Some(team), seasons))?);
+        writeln!(html, "</div>")?;
+    }
+    writeln!(html, "</body>\n</html>")?;
+    Ok(html)
+}
+
+pub fn write_html_report(games: &[Game], teams: &HashSet<String>, seasons: &[usize], league: &str, path: &str) -> Result<(), Box<dyn Error>> {
+    // writes the report page to a file.
+    std::fs::write(path, html_report(games, teams, seasons, league)?)?;
+    Ok(())
+}
@@ -0,0 +1,159 @@
+use std::collections::HashSet;
+use std::error::Error;
+use serde_json::{json, Value};
+use tiny_http::{Header, Response, Server};
+use crate::Game;
+use crate::game::MyError;
+use crate::advantage::{home_edge, matchweek_series};
+use crate::calendar::{by_month, by_weekday};
+use crate::records::{leaderboard, Ranking};
+use crate::standings::league_table;
+use crate::wins::{away_pct, draw_pct, home_pct};
+
+// a module that serves the match analytics as JSON over localhost, built on the same functions main prints from.
+// the games are loaded once in main and shared by every request.
+
+fn decode(segment: &str) -> String {
+    // percent-decodes one piece of a URL, so "Nott%27ham%20Forest" becomes "Nott'ham Forest". a '%' not followed by
+    // two hex digits is kept as it is.
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() && bytes[i + 1].is_ascii_hexdigit() && bytes[i + 2].is_ascii_hexdigit() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap();
+            decoded.push(u8::from_str_radix(hex, 16).unwrap());
+            i += 3;
+            continue;
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+fn query_param(query: &str, key: &str) -> Option<String> {
+    // looks up one key in a query string like "group=season&window=10". a '+' in a query value is a space, unlike in the path.
+    query.split('&').find_map(|pair| {
+        let (k, v) = pair.split_once('=')?;
+        if k == key { Some(decode(&v.replace('+', " "))) } else { None }
+    })
+}
+
+fn not_found(message: String) -> (u16, Value) {
+    (404, json!({ "error": message }))
+}
+
+fn team_summary(games: &[Game], team: &String, seasons: &[usize]) -> Value {
+    // a team's record across every season it played in.
+    let (played, count) = crate::team_seasons(games, team, seasons);
+    let mut per_season = Vec::new();
+    for season in &played {
+        let table = league_table(games, *season);
+        let position = table.iter().position(|r| r.team == *team).unwrap();
+        per_season.push(json!({
+            "season": season,
+            "position": position + 1,
+            "record": table[position],
+            "home_edge": home_edge(games, Some(team), *season),
+        }));
+    }
+    json!({
+        "team": team,
+        "seasons": count,
+        "win_rate": crate::team_win_rate(games, team, seasons),
+        "biggest_win": leaderboard(games, Ranking::BiggestWins, Some(team), seasons, 1).pop(),
+        "heaviest_defeat": leaderboard(games, Ranking::HeaviestDefeats, Some(team), seasons, 1).pop(),
+        "by_season": per_season,
+    })
+}
+
+fn rates(games: &[Game], seasons: &[usize], query: &str) -> (u16, Value) {
+    // result rates grouped by season, matchweek, weekday or month.
+    let group = query_param(query, "group").unwrap_or_else(|| "season".to_string());
+    match group.as_str() {
+        "season" => {
+            let mut rows = Vec::new();
+            for season in seasons {
+                rows.push(json!({
+                    "season": season,
+                    "home_win_rate": home_pct(games, *season),
+                    "draw_rate": draw_pct(games, *season),
+                    "away_win_rate": away_pct(games, *season),
+                    "goals_per_game": crate::goal_avg(games, *season),
+                    "home_edge": home_edge(games, None, *season),
+                }));
+            }
+            (200, json!(rows))
+        },
+        "matchweek" => {
+            let window = query_param(query, "window").and_then(|w| w.parse::<usize>().ok()).unwrap_or(1);
+            (200, json!(matchweek_series(games, window)))
+        },
+        "weekday" => (200, json!(by_weekday(games))),
+        "month" => (200, json!(by_month(games))),
+        _ => (400, json!({ "error": format!("Unknown group '{}'. Use season, matchweek, weekday or month.", group) })),
+    }
+}
+
+pub fn route(games: &[Game], teams: &HashSet<String>, seasons: &[usize], url: &str) -> (u16, Value) {
+    // answers one request: returns the status code and the JSON body for a URL like "/seasons/2021/table".
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let parts: Vec<String> = path.split('/').filter(|p| !p.is_empty()).map(decode).collect();
+    let parts: Vec<&str> = parts.iter().map(|p| p.as_str()).collect();
+    match parts.as_slice() {
+        ["teams"] => {
+            let mut names: Vec<&String> = teams.iter().collect();
+            names.sort();
+            (200, json!(names))
+        },
+        ["seasons"] => (200, json!(seasons)),
+        ["seasons", year, "table"] => match year.parse::<usize>() {
+            Ok(season) if seasons.contains(&season) => (200, json!(league_table(games, season))),
+            _ => not_found(format!("No season {}", year)),
+        },
+        ["teams", name, "summary"] => {
+            let team = name.to_string();
+            if !teams.contains(&team) {
+                return not_found(format!("No team called {}", name));
+            }
+            (200, team_summary(games, &team, seasons))
+        },
+        ["h2h", a, b] => {
+            let (team_a, team_b) = (a.to_string(), b.to_string());
+            if !teams.contains(&team_a) || !teams.contains(&team_b) {
+                return not_found(format!("No team called {}", if teams.contains(&team_a) { b } else { a }));
+            }
+            let meetings = crate::head_to_head(games, &team_a, &team_b, seasons);
+            let wins_for = |team: &String| meetings.iter().filter(|g| (g.home == *team && g.result == "H") || (g.away == *team && g.result == "A")).count();
+            (200, json!({
+                "teams": [team_a, team_b],
+                "played": meetings.len(),
+                "wins": [wins_for(&team_a), wins_for(&team_b)],
+                "draws": meetings.iter().filter(|g| g.result == "D").count(),
+                "games": meetings,
+            }))
+        },
+        ["rates"] => rates(games, seasons, query),
+        _ => not_found(format!("No endpoint at {}", path)),
+    }
+}
+
+pub fn serve(games: &[Game], teams: &HashSet<String>, seasons: &[usize], port: u16) -> Result<(), Box<dyn Error>> {
+    // listens on localhost until the process is stopped, answering each request with `route`.
+    let server = Server::http(("127.0.0.1", port)).map_err(|e| MyError(e.to_string()))?;
+    println!("Serving the match data on http://localhost:{}", port);
+    let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+    let cors = Header::from_bytes("Access-Control-Allow-Origin", "*").unwrap(); // so a front-end on another local port can call it
+    for request in server.incoming_requests() {
+        let (status, body) = route(games, teams, seasons, request.url());
+        let response = Response::from_string(body.to_string())
+            .with_status_code(status)
+            .with_header(content_type.clone())
+            .with_header(cors.clone());
+        if let Err(e) = request.respond(response) {
+            println!("Could not send a response: {}", e); // the client hung up; keep serving everyone else
+        }
+    }
+    Ok(())
+}
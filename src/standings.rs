@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use serde::Serialize;
+use crate::Game;
+
+// a module to rebuild the league table for a season from its results.
+
+pub const DEDUCTIONS: [(&str, usize, &str, usize); 2] = [("Premier League", 1997, "Middlesbrough", 3), ("Premier League", 2010, "Portsmouth", 9)]; // (competition, season, team, points) deducted by the league
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TableRow {
+    // one team's line in the league table.
+    pub team: String,
+    pub played: usize,
+    pub won: usize,
+    pub drawn: usize,
+    pub lost: usize,
+    pub goals_for: usize,
+    pub goals_against: usize,
+    pub points: usize,
+}
+
+impl TableRow {
+    pub fn goal_difference(&self) -> i64 {
+        self.goals_for as i64 - self.goals_against as i64
+    }
+
+    fn add(&mut self, scored: usize, conceded: usize) {
+        // records one game from this team's point of view.
+        self.played += 1;
+        self.goals_for += scored;
+        self.goals_against += conceded;
+        if scored > conceded {
+            self.won += 1;
+            self.points += 3;
+        } else if scored == conceded {
+            self.drawn += 1;
+            self.points += 1;
+        } else {
+            self.lost += 1;
+        }
+    }
+}
+
+pub fn league_table(games: &[Game], season: usize) -> Vec<TableRow> {
+    // the final table for a season, ordered by points, then goal difference, then goals scored.
+    let mut rows: HashMap<&String, TableRow> = HashMap::new();
+    for game in games.iter().filter(|g| g.season == season) {
+        rows.entry(&game.home).or_insert_with(|| TableRow { team: game.home.clone(), ..Default::default() }).add(game.home_goals, game.away_goals);
+        rows.entry(&game.away).or_insert_with(|| TableRow { team: game.away.clone(), ..Default::default() }).add(game.away_goals, game.home_goals);
+    }
+    let mut table: Vec<TableRow> = rows.into_values().collect();
+    for (competition, deduction_season, team, points) in DEDUCTIONS {
+        // only a team that played that season in the competition that docked it, not a namesake in another league.
+        let docked = games.iter().any(|g| g.season == season && g.competition == competition && (g.home == team || g.away == team));
+        if deduction_season == season && docked {
+            if let Some(row) = table.iter_mut().find(|r| r.team == team) {
+                row.points = row.points.saturating_sub(points);
+            }
+        }
+    }
+    table.sort_by(|a, b| {
+        b.points.cmp(&a.points)
+            .then(b.goal_difference().cmp(&a.goal_difference()))
+            .then(b.goals_for.cmp(&a.goals_for))
+            .then(a.team.cmp(&b.team))
+    });
+    table
+}
+
+pub fn positions_by_week(games: &[Game], season: usize, team: &String) -> Vec<(usize, usize)> {
+    // the team's league position after each matchweek of a season, as (week, position). games count towards the matchweek
+    // they were scheduled in, so a postponed game moves the table in its original week, and a points deduction applies all season.
+    let season_games: Vec<Game> = games.iter().filter(|g| g.season == season).cloned().collect();
+    let weeks = season_games.iter().map(|g| g.week).max().unwrap_or(0);
+    (1..=weeks)
+        .filter_map(|week| {
+            let played: Vec<Game> = season_games.iter().filter(|g| g.week <= week).cloned().collect();
+            league_table(&played, season).iter().position(|r| r.team == *team).map(|i| (week, i + 1))
+        })
+        .collect()
+}
+
+pub fn relegated(games: &[Game], season: usize) -> Vec<String> {
+    // the teams relegated at the end of a season, in table order.
+    // when the next season is in the data, these are the teams missing from it (which also handles 1995, when four went down);
+    // otherwise the bottom three are assumed.
+    let table = league_table(games, season);
+    let next: Vec<&Game> = games.iter().filter(|g| g.season == season + 1).collect();
+    if next.is_empty() {
+        return table.iter().rev().take(3).rev().map(|r| r.team.clone()).collect();
+    }
+    table
+        .iter()
+        .filter(|r| !next.iter().any(|g| g.home == r.team || g.away == r.team))
+        .map(|r| r.team.clone())
+        .collect()
+}
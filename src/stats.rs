@@ -0,0 +1,143 @@
+use serde::Serialize;
+
+// a module for the general statistics the analyses share.
+
+pub const MAX_GOALS: usize = 15; // goals beyond this have a negligible Poisson probability at league scoring rates
+
+pub fn correlation(pairs: &[(f64, f64)]) -> f64 {
+    // the Pearson correlation between the two halves of each pair.
+    let n = pairs.len() as f64;
+    let mean_x = pairs.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = pairs.iter().map(|p| p.1).sum::<f64>() / n;
+    let mut cov = 0.0;
+    let mut var_x = 0.0;
+    let mut var_y = 0.0;
+    for (x, y) in pairs {
+        cov += (x - mean_x) * (y - mean_y);
+        var_x += (x - mean_x).powi(2);
+        var_y += (y - mean_y).powi(2);
+    }
+    cov / (var_x.sqrt() * var_y.sqrt())
+}
+
+pub fn poisson(k: usize, rate: f64) -> f64 {
+    // the probability of exactly k goals at an average of `rate` per game.
+    let mut p = (-rate).exp();
+    for i in 1..=k {
+        p *= rate / i as f64;
+    }
+    p
+}
+
+pub const Z_95: f64 = 1.959964; // the normal quantile for a two-sided 95% interval
+pub const RESAMPLES: usize = 1000; // bootstrap resamples, enough for a stable 95% interval
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Estimate {
+    // a value with its standard error and a 95% confidence interval.
+    pub value: f64,
+    pub standard_error: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+impl Estimate {
+    pub fn scaled(&self, factor: f64) -> Estimate {
+        // the same estimate in other units, e.g. a proportion as a percentage.
+        Estimate { value: self.value * factor, standard_error: self.standard_error * factor, lower: self.lower * factor, upper: self.upper * factor }
+    }
+
+    pub fn describe(&self, decimals: usize) -> String {
+        format!("{:.*} (95% CI {:.*} to {:.*}, SE {:.*})", decimals, self.value, decimals, self.lower, decimals, self.upper, decimals, self.standard_error)
+    }
+}
+
+pub struct Rng(u64);
+
+impl Rng {
+    // a small SplitMix64 generator. seeded, so the bootstrap gives the same intervals on every run.
+    pub fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        // a whole number from 0 to n - 1. the modulo bias is negligible for the sizes used here.
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+pub fn wilson(successes: usize, trials: usize) -> Estimate {
+    // a proportion with its Wilson score interval, which unlike the textbook p ± 1.96 SE stays inside 0 to 1 and behaves
+    // for small samples and rates near 0 or 1. the standard error is the usual sqrt(p(1 - p) / n).
+    if trials == 0 {
+        return Estimate { value: 0.0, standard_error: 0.0, lower: 0.0, upper: 1.0 };
+    }
+    let n = trials as f64;
+    let p = successes as f64 / n;
+    let z2 = Z_95 * Z_95;
+    let centre = (p + z2 / (2.0 * n)) / (1.0 + z2 / n);
+    let half_width = Z_95 / (1.0 + z2 / n) * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt();
+    Estimate { value: p, standard_error: (p * (1.0 - p) / n).sqrt(), lower: centre - half_width, upper: centre + half_width }
+}
+
+pub fn bootstrap_mean(values: &[f64], rng: &mut Rng) -> Estimate {
+    // the mean with a percentile bootstrap interval: the values are resampled with replacement RESAMPLES times,
+    // and the middle 95% of the resampled means is the interval. the standard error is their standard deviation.
+    let n = values.len();
+    if n == 0 {
+        return Estimate { value: 0.0, standard_error: 0.0, lower: 0.0, upper: 0.0 };
+    }
+    let mean = values.iter().sum::<f64>() / n as f64;
+    let mut means: Vec<f64> = (0..RESAMPLES).map(|_| (0..n).map(|_| values[rng.below(n)]).sum::<f64>() / n as f64).collect();
+    means.sort_by(|a, b| a.total_cmp(b));
+    let spread = (means.iter().map(|m| (m - mean).powi(2)).sum::<f64>() / (RESAMPLES - 1) as f64).sqrt();
+    let tail = (RESAMPLES as f64 * 0.025) as usize;
+    Estimate { value: mean, standard_error: spread, lower: means[tail], upper: means[RESAMPLES - 1 - tail] }
+}
+
+pub fn normal_cdf(x: f64) -> f64 {
+    // the standard normal distribution function, from the Abramowitz and Stegun approximation to erfc (accurate to about 1e-7).
+    let z = x.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.3275911 * z);
+    let polynomial = t * (0.254829592 + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let upper_tail = 0.5 * polynomial * (-z * z).exp();
+    if x >= 0.0 { 1.0 - upper_tail } else { upper_tail }
+}
+
+pub fn invert(matrix: &[Vec<f64>]) -> Option<Vec<Vec<f64>>> {
+    // the inverse of a square matrix by Gauss-Jordan elimination with partial pivoting, or None if it is singular.
+    let n = matrix.len();
+    let mut left: Vec<Vec<f64>> = matrix.to_vec();
+    let mut right: Vec<Vec<f64>> = (0..n).map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect()).collect();
+    for column in 0..n {
+        let pivot = (column..n).max_by(|a, b| left[*a][column].abs().total_cmp(&left[*b][column].abs()))?;
+        if left[pivot][column].abs() < 1e-12 {
+            return None;
+        }
+        left.swap(column, pivot);
+        right.swap(column, pivot);
+        let scale = left[column][column];
+        for j in 0..n {
+            left[column][j] /= scale;
+            right[column][j] /= scale;
+        }
+        for row in 0..n {
+            if row != column && left[row][column] != 0.0 {
+                let factor = left[row][column];
+                for j in 0..n {
+                    left[row][j] -= factor * left[column][j];
+                    right[row][j] -= factor * right[column][j];
+                }
+            }
+        }
+    }
+    Some(right)
+}
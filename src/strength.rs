@@ -0,0 +1,151 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use serde::Serialize;
+use crate::Game;
+use crate::ratings::{expected_score, pre_game_ratings, win_probability, HOME_ADVANTAGE, INITIAL};
+use crate::standings::league_table;
+
+// a module for strength of schedule: how good the opponents a team has faced so far were, so a table early in the season
+// can be read knowing who has had the hard fixtures, and a team's results can be corrected for who was played.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OpponentStrength {
+    Elo, // the opponent's Elo rating going into the game
+    PointsPerGame, // the opponent's points per game over the whole season
+}
+
+impl OpponentStrength {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            OpponentStrength::Elo => "Elo rating",
+            OpponentStrength::PointsPerGame => "points per game",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SchedulePoint {
+    // a team's schedule up to and including one of its games: the average strength of the opponents so far, overall and by venue.
+    pub season: usize,
+    pub week: usize,
+    pub team: String,
+    pub games: usize,
+    pub points: usize,
+    pub opponent_strength: f64,
+    pub home_opponent_strength: Option<f64>, // the opponents it hosted
+    pub away_opponent_strength: Option<f64>, // the opponents it visited
+}
+
+fn opponent_strengths(games: &[Game], measure: OpponentStrength) -> Vec<Option<(f64, f64)>> {
+    // for every game, the strength of the home side's opponent and of the away side's opponent, or None if either
+    // side is missing from its season's table.
+    match measure {
+        OpponentStrength::Elo => pre_game_ratings(games).into_iter().map(|(home, away)| Some((away, home))).collect(),
+        OpponentStrength::PointsPerGame => {
+            let mut ppg: HashMap<(usize, String), f64> = HashMap::new();
+            for season in crate::game::seasons_in(games) {
+                for row in league_table(games, season) {
+                    ppg.insert((season, row.team.clone()), row.points as f64 / row.played as f64);
+                }
+            }
+            games.iter().map(|g| Some((*ppg.get(&(g.season, g.away.clone()))?, *ppg.get(&(g.season, g.home.clone()))?))).collect()
+        },
+    }
+}
+
+pub fn strength_of_schedule(games: &[Game], season: usize, measure: OpponentStrength) -> Vec<SchedulePoint> {
+    // every team's running strength of schedule through a season, one point after each of its games in the order they were played.
+    let strengths = opponent_strengths(games, measure);
+    let mut order: Vec<usize> = (0..games.len()).filter(|i| games[*i].season == season).collect();
+    order.sort_by_key(|i| games[*i].date);
+    let mut running: HashMap<&String, (usize, [f64; 2], [usize; 2])> = HashMap::new(); // team -> (points, total strength by venue, games by venue)
+    let mut points = Vec::new();
+    for i in order {
+        let game = &games[i];
+        let Some((home_opponent, away_opponent)) = strengths[i] else {
+            continue; // no strength to add for this game, so it's left out of both sides' schedules
+        };
+        let sides = [(&game.home, game.home_goals, game.away_goals, home_opponent, 0), (&game.away, game.away_goals, game.home_goals, away_opponent, 1)];
+        for (team, scored, conceded, strength, venue) in sides {
+            let (team_points, totals, counts) = running.entry(team).or_insert((0, [0.0; 2], [0; 2]));
+            *team_points += crate::advantage::points(scored, conceded);
+            totals[venue] += strength;
+            counts[venue] += 1;
+            let average = |v: usize| if counts[v] == 0 { None } else { Some(totals[v] / counts[v] as f64) };
+            points.push(SchedulePoint {
+                season,
+                week: game.week,
+                team: team.clone(),
+                games: counts[0] + counts[1],
+                points: *team_points,
+                opponent_strength: (totals[0] + totals[1]) / (counts[0] + counts[1]) as f64,
+                home_opponent_strength: average(0),
+                away_opponent_strength: average(1),
+            });
+        }
+    }
+    points
+}
+
+pub fn schedule_after(games: &[Game], season: usize, week: usize, measure: OpponentStrength) -> Vec<SchedulePoint> {
+    // each team's strength of schedule after the given matchweek, toughest first.
+    let mut latest: HashMap<String, SchedulePoint> = HashMap::new();
+    for point in strength_of_schedule(games, season, measure).into_iter().filter(|p| p.week <= week) {
+        latest.insert(point.team.clone(), point); // the points come in date order, so the last one for each team wins
+    }
+    let mut table: Vec<SchedulePoint> = latest.into_values().collect();
+    table.sort_by(|a, b| b.opponent_strength.total_cmp(&a.opponent_strength).then(a.team.cmp(&b.team)));
+    table
+}
+
+fn team_games(games: &[Game], team: &String, seasons: &[usize]) -> Vec<(f64, f64, Ordering)> {
+    // the team's games in the seasons as (home advantage in rating points, the opponent's rating going in, the result for the team).
+    let ratings = pre_game_ratings(games);
+    let mut played = Vec::new();
+    for (game, (home_rating, away_rating)) in games.iter().zip(&ratings) {
+        if !seasons.contains(&game.season) {
+            continue;
+        }
+        if game.home == *team {
+            played.push((HOME_ADVANTAGE, *away_rating, game.home_goals.cmp(&game.away_goals)));
+        } else if game.away == *team {
+            played.push((-HOME_ADVANTAGE, *home_rating, game.away_goals.cmp(&game.home_goals)));
+        }
+    }
+    played
+}
+
+pub fn adjusted_win_rate(games: &[Game], team: &String, seasons: &[usize]) -> Option<f64> {
+    // team_win_rate corrected for the opponents faced. for each game, Elo with draws gives the chance of an average team winning
+    // against an average opponent and against the actual opponent at the same venue; the win rate is raised by the average
+    // shortfall between the two, so a team that played strong sides gets credit for it and one that played weak sides loses some.
+    // both chances are of a win, so the shortfall is on the same scale as the win rate it corrects.
+    let played = team_games(games, team, seasons);
+    if played.is_empty() {
+        return None;
+    }
+    let draw_rate = games.iter().filter(|g| g.result == "D").count() as f64 / games.len() as f64;
+    let shortfall: f64 = played.iter().map(|(venue, opponent, _)| win_probability(*venue, draw_rate) - win_probability(INITIAL + venue - opponent, draw_rate)).sum();
+    Some(crate::team_win_rate(games, team, seasons) + shortfall / played.len() as f64 * 100.0)
+}
+
+pub fn adjusted_score_rate(games: &[Game], team: &String, seasons: &[usize]) -> Option<(f64, f64)> {
+    // the team's score rate (wins plus half its draws, as a percentage of games) before and after correcting for the opponents
+    // faced, as (actual, adjusted). this is the same correction as adjusted_win_rate, on the scale of Elo's expected score.
+    let played = team_games(games, team, seasons);
+    if played.is_empty() {
+        return None;
+    }
+    let mut score = 0.0;
+    let mut shortfall = 0.0;
+    for (venue, opponent, result) in &played {
+        score += match result {
+            Ordering::Greater => 1.0,
+            Ordering::Equal => 0.5,
+            Ordering::Less => 0.0,
+        };
+        shortfall += expected_score(*venue) - expected_score(INITIAL + venue - opponent);
+    }
+    let actual = score / played.len() as f64 * 100.0;
+    Some((actual, actual + shortfall / played.len() as f64 * 100.0))
+}
@@ -0,0 +1,127 @@
+use crate::Game;
+use crate::records::{leaderboard, Ranking};
+use crate::standings::{league_table, relegated, TableRow};
+use crate::wins::{away_pct, draw_pct, home_pct};
+
+// a module that gathers everything worth knowing about one season into a single report.
+
+type RunTest = fn(usize, usize) -> bool; // given (scored, conceded), whether a game continues a run
+
+#[derive(Debug, Clone)]
+pub struct Streak {
+    // the longest run of a kind of result by any team in a season.
+    pub kind: &'static str,
+    pub team: String,
+    pub length: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct SeasonSummary {
+    pub season: usize,
+    pub champion: String,
+    pub relegated: Vec<String>,
+    pub table: Vec<TableRow>,
+    pub top_scoring: TableRow,
+    pub best_defence: TableRow,
+    pub biggest_win: Option<Game>,
+    pub home_rate: f64,
+    pub away_rate: f64,
+    pub draw_rate: f64,
+    pub goals_per_game: f64,
+    pub all_time_goals_per_game: f64,
+    pub streaks: Vec<Streak>,
+}
+
+fn longest_run(games: &[Game], season: usize, team: &String, counts: RunTest) -> usize {
+    // the longest run of consecutive games in a season where `counts(scored, conceded)` holds for the team.
+    let mut team_games: Vec<&Game> = games.iter().filter(|g| g.season == season && (g.home == *team || g.away == *team)).collect();
+    team_games.sort_by_key(|g| g.date);
+    let mut longest = 0;
+    let mut current = 0;
+    for game in team_games {
+        let (scored, conceded) = if game.home == *team { (game.home_goals, game.away_goals) } else { (game.away_goals, game.home_goals) };
+        if counts(scored, conceded) {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 0;
+        }
+    }
+    longest
+}
+
+fn season_streaks(games: &[Game], season: usize, table: &[TableRow]) -> Vec<Streak> {
+    // the longest winning, unbeaten, losing and winless runs of the season. ties go to the team higher in the table.
+    let kinds: [(&'static str, RunTest); 4] = [
+        ("winning", |s, c| s > c),
+        ("unbeaten", |s, c| s >= c),
+        ("losing", |s, c| s < c),
+        ("winless", |s, c| s <= c),
+    ];
+    let mut streaks = Vec::new();
+    for (kind, counts) in kinds {
+        let mut best = Streak { kind, team: String::new(), length: 0 };
+        for row in table {
+            let length = longest_run(games, season, &row.team, counts);
+            if length > best.length {
+                best = Streak { kind, team: row.team.clone(), length };
+            }
+        }
+        streaks.push(best);
+    }
+    streaks
+}
+
+pub fn season_summary(games: &[Game], season: usize, all_seasons: &[usize]) -> Option<SeasonSummary> {
+    // builds the full report for a season, or None if there are no games for it.
+    let table = league_table(games, season);
+    if table.is_empty() {
+        return None;
+    }
+    let top_scoring = table.iter().max_by(|a, b| a.goals_for.cmp(&b.goals_for).then(b.goals_against.cmp(&a.goals_against))).unwrap().clone();
+    let best_defence = table.iter().min_by(|a, b| a.goals_against.cmp(&b.goals_against).then(b.goals_for.cmp(&a.goals_for))).unwrap().clone();
+    let mut all_time = 0.0;
+    for szn in all_seasons {
+        all_time += crate::goal_avg(games, *szn);
+    }
+    Some(SeasonSummary {
+        season,
+        champion: table[0].team.clone(),
+        relegated: relegated(games, season),
+        top_scoring,
+        best_defence,
+        biggest_win: leaderboard(games, Ranking::BiggestWins, None, &[season], 1).pop(),
+        home_rate: home_pct(games, season),
+        away_rate: away_pct(games, season),
+        draw_rate: draw_pct(games, season),
+        goals_per_game: crate::goal_avg(games, season),
+        all_time_goals_per_game: all_time / all_seasons.len() as f64,
+        streaks: season_streaks(games, season, &table),
+        table,
+    })
+}
+
+impl SeasonSummary {
+    pub fn print(&self) {
+        // prints the report as a table followed by the season's talking points.
+        println!("The {} season", self.season);
+        println!();
+        println!("{:>3} {:<18} {:>3} {:>3} {:>3} {:>3} {:>4} {:>4} {:>4} {:>4}", "Pos", "Team", "P", "W", "D", "L", "GF", "GA", "GD", "Pts");
+        for (i, row) in self.table.iter().enumerate() {
+            println!("{:>3} {:<18} {:>3} {:>3} {:>3} {:>3} {:>4} {:>4} {:>4} {:>4}", i + 1, row.team, row.played, row.won, row.drawn, row.lost, row.goals_for, row.goals_against, row.goal_difference(), row.points);
+        }
+        println!();
+        println!("{} won the league with {} points.", self.champion, self.table[0].points);
+        println!("Relegated: {}.", self.relegated.join(", "));
+        println!("{} scored the most goals with {}, and {} had the best defence, conceding {}.", self.top_scoring.team, self.top_scoring.goals_for, self.best_defence.team, self.best_defence.goals_against);
+        println!("Home wins made up {:.3}% of results, away wins {:.3}% and draws {:.3}%.", self.home_rate, self.away_rate, self.draw_rate);
+        println!("There were {:.4} goals per game, compared to the all-time average of {:.4}.", self.goals_per_game, self.all_time_goals_per_game);
+        for streak in &self.streaks {
+            println!("Longest {} run: {} with {} games.", streak.kind, streak.team, streak.length);
+        }
+        if let Some(game) = &self.biggest_win {
+            println!("The biggest win of the season:");
+            game.print();
+        }
+    }
+}
@@ -1,8 +1,9 @@
 use crate::Game;
+use crate::stats::{wilson, Estimate};
 
 // a module containing functions that are used to compute result rates in a given season.
 
-pub fn home_pct(games: &Vec<Game>, season: usize) -> f64 {
+pub fn home_pct(games: &[Game], season: usize) -> f64 {
     // calculates the percentage of games in a season where the home team won.
     let mut home_win = 0;
     let mut game_count = 0;
@@ -17,7 +18,7 @@ pub fn home_pct(games: &Vec<Game>, season: usize) -> f64 {
     return (home_win as f64)/(game_count as f64) * 100.0;
 }
 
-pub fn draw_pct(games: &Vec<Game>, season: usize) -> f64 {
+pub fn draw_pct(games: &[Game], season: usize) -> f64 {
     // calculates the percentage of games in a season that ended in a draw.
     let mut draw = 0;
     let mut game_count = 0;
@@ -32,7 +33,7 @@ pub fn draw_pct(games: &Vec<Game>, season: usize) -> f64 {
     return (draw as f64)/(game_count as f64) * 100.0;
 }
 
-pub fn away_pct(games: &Vec<Game>, season: usize) -> f64 {
+pub fn away_pct(games: &[Game], season: usize) -> f64 {
     // calculates the percentage of games in a season where the away team won.
     let mut away_win = 0;
     let mut game_count = 0;
@@ -45,4 +46,26 @@ pub fn away_pct(games: &Vec<Game>, season: usize) -> f64 {
         }
     }
     return (away_win as f64)/(game_count as f64) * 100.0;
-}
\ No newline at end of file
+}
+
+pub fn result_estimate(games: &[Game], season: usize, result: &str) -> Estimate {
+    // the percentage of games in a season with the given result ("H", "D" or "A"), with its Wilson interval.
+    let season_games: Vec<&Game> = games.iter().filter(|g| g.season == season).collect();
+    let matching = season_games.iter().filter(|g| g.result == result).count();
+    wilson(matching, season_games.len()).scaled(100.0)
+}
+
+pub fn home_estimate(games: &[Game], season: usize) -> Estimate {
+    // home_pct with a confidence interval.
+    result_estimate(games, season, "H")
+}
+
+pub fn draw_estimate(games: &[Game], season: usize) -> Estimate {
+    // draw_pct with a confidence interval.
+    result_estimate(games, season, "D")
+}
+
+pub fn away_estimate(games: &[Game], season: usize) -> Estimate {
+    // away_pct with a confidence interval.
+    result_estimate(games, season, "A")
+}